@@ -77,15 +77,142 @@ pub struct OverlaySettings {
     pub image: Option<ImageOverlay>,
 }
 
+/// A frame rate or aspect value expressed as `num/den`, e.g. `30000/1001`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct Rational {
+    pub num: u32,
+    pub den: u32,
+}
+
+impl Rational {
+    /// Parse an FFprobe rational string like "30000/1001". Returns `None` for
+    /// the "0/0" placeholder FFmpeg emits when a value is unknown.
+    pub fn parse(raw: &str) -> Option<Rational> {
+        let (num, den) = raw.split_once('/')?;
+        let num: u32 = num.trim().parse().ok()?;
+        let den: u32 = den.trim().parse().ok()?;
+        if den == 0 {
+            return None;
+        }
+        Some(Rational { num, den })
+    }
+}
+
+/// Per-stream properties, typed by `codec_type`.
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct MediaInfo {
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum StreamProps {
+    Video {
+        width: Option<u32>,
+        height: Option<u32>,
+        pix_fmt: Option<String>,
+        avg_frame_rate: Option<Rational>,
+        /// Display rotation in degrees derived from the display-matrix side data.
+        rotation: i32,
+    },
+    Audio {
+        sample_rate: Option<u32>,
+        channels: Option<u32>,
+        channel_layout: Option<String>,
+    },
+    Subtitle {
+        language: Option<String>,
+    },
+    /// Data/attachment streams we don't model in detail.
+    Other,
+}
+
+/// A single elementary stream inside the container.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MediaStream {
+    pub index: u32,
+    pub codec_type: String,
+    pub codec_name: Option<String>,
+    pub props: StreamProps,
+}
+
+/// Container-level metadata from `-show_format`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MediaFormat {
+    pub format_name: Option<String>,
     pub duration: f64,
-    pub width: Option<u32>,
-    pub height: Option<u32>,
-    pub codec: Option<String>,
-    pub audio_codec: Option<String>,
-    pub format: Option<String>,
     pub bitrate: Option<u64>,
+    pub size: Option<u64>,
+    pub tags: std::collections::HashMap<String, String>,
+}
+
+/// A chapter marker from `-show_chapters`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MediaChapter {
+    pub start: f64,
+    pub end: f64,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MediaInfo {
+    pub format: MediaFormat,
+    pub streams: Vec<MediaStream>,
+    pub chapters: Vec<MediaChapter>,
+}
+
+/// A timestamped caption burned against the trimmed timeline.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineCaption {
+    /// Original-source time the caption appears.
+    pub at: f64,
+    /// Original-source time the caption disappears.
+    pub until: f64,
+    pub text: String,
+}
+
+/// Declarative multi-segment render: cut to `[start, end]`, speed up `fast`
+/// sub-ranges, and burn in timestamped `questions`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineSettings {
+    pub start: f64,
+    pub end: f64,
+    /// `[from, to]` ranges (in source time) to speed up.
+    #[serde(default)]
+    pub fast: Vec<[f64; 2]>,
+    /// Speed-up factor applied to `fast` ranges. Defaults to 2.0 when unset.
+    #[serde(default)]
+    pub fast_factor: f64,
+    #[serde(default)]
+    pub questions: Vec<TimelineCaption>,
+}
+
+/// Hardware-accelerated encode configuration.
+///
+/// Selects a GPU backend for decode, filtering, and encode. When unset the
+/// pipeline stays on the software (CPU) path.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HwAccel {
+    /// One of "vaapi", "nvenc", "qsv", or "videotoolbox".
+    pub backend: String,
+    /// Optional device node / index (e.g. "/dev/dri/renderD128").
+    pub device: Option<String>,
+}
+
+/// Adaptive-streaming / segmented output configuration.
+///
+/// When present on [`ConvertOptions`], the command layer packages the output as
+/// a playlist plus numbered segments directory instead of a single file.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SegmentSettings {
+    /// One of "hls", "dash", or "cmaf".
+    pub mode: String,
+    /// Target segment length in seconds.
+    pub segment_duration: f64,
+    /// Playlist / manifest filename written inside the output directory.
+    pub playlist_name: String,
+    /// Emit low-latency chunks so playback can start before a segment finishes.
+    #[serde(default)]
+    pub low_latency: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -96,6 +223,42 @@ pub struct ConvertOptions {
     pub audio_codec: Option<String>,
     pub crf: Option<u8>,
     pub preset: Option<String>,
+    /// Target average video bitrate in bits/s (enables bitrate-based encoding).
+    #[serde(default)]
+    pub bitrate: Option<u64>,
+    #[serde(default)]
+    pub max_bitrate: Option<u64>,
+    #[serde(default)]
+    pub bufsize: Option<u64>,
+    /// Run a two-pass encode for accurate bitrate/size targeting.
+    #[serde(default)]
+    pub two_pass: bool,
+    /// Render a near-lossless intermediate once and reuse it as the encode
+    /// input, avoiding repeated decodes of an expensive source filter chain.
+    #[serde(default)]
+    pub preprocess: bool,
+    /// Split the input into scene-based chunks and encode them concurrently.
+    #[serde(default)]
+    pub scene_split: bool,
+    /// Maximum number of concurrent chunk encoders (defaults to the number of
+    /// available CPUs when unset).
+    #[serde(default)]
+    pub parallel: Option<u32>,
+    /// Target mean VMAF score; when set, CRF is auto-selected to hit it.
+    #[serde(default)]
+    pub target_vmaf: Option<f32>,
+    /// `[from, to]` source-time ranges to speed up.
+    #[serde(default)]
+    pub fast: Vec<[f64; 2]>,
+    /// Speed-up factor for `fast` ranges (defaults to 2.0).
+    #[serde(default)]
+    pub fast_speed: Option<f64>,
+    /// `[from, to]` source-time ranges to slow down.
+    #[serde(default)]
+    pub slow: Vec<[f64; 2]>,
+    /// Speed factor (<1.0) for `slow` ranges (defaults to 0.5).
+    #[serde(default)]
+    pub slow_speed: Option<f64>,
     pub start_time: Option<f64>,
     pub end_time: Option<f64>,
     pub width: Option<u32>,
@@ -109,8 +272,12 @@ pub struct ConvertOptions {
     pub playback_speed: Option<f32>,
     pub export_gif: Option<bool>,
     pub extract_thumbnail: Option<bool>,
-    pub hw_accel: Option<bool>,
-    
+    pub hw_accel: Option<HwAccel>,
+    #[serde(default)]
+    pub segment: Option<SegmentSettings>,
+    #[serde(default)]
+    pub timeline: Option<TimelineSettings>,
+
     // New Fields
     #[serde(rename = "audioFilters")] 
     pub audio_filters: Option<AudioFilterSettings>,
@@ -118,6 +285,88 @@ pub struct ConvertOptions {
     pub video_transform: Option<VideoTransformSettings>,
 }
 
+/// One rung of an adaptive-bitrate ladder.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Rendition {
+    pub width: u32,
+    pub height: u32,
+    /// Target video bitrate in bits/s.
+    pub video_bitrate: u64,
+    /// Target audio bitrate in bits/s.
+    pub audio_bitrate: u64,
+}
+
+/// A single selectable download format reported by yt-dlp.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoFormat {
+    pub format_id: String,
+    pub ext: String,
+    pub resolution: Option<String>,
+    pub filesize: Option<u64>,
+}
+
+/// Structured metadata for a single video entry.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoInfo {
+    pub title: String,
+    pub uploader: Option<String>,
+    pub duration: Option<f64>,
+    pub thumbnail: Option<String>,
+    pub formats: Vec<VideoFormat>,
+}
+
+/// A playlist and its entries.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistInfo {
+    pub title: Option<String>,
+    pub entries: Vec<VideoInfo>,
+}
+
+/// yt-dlp output is either a single video or a playlist, mirroring the
+/// `youtube_dl` crate's `YoutubeDlOutput`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum YoutubeDlOutput {
+    Playlist(PlaylistInfo),
+    Video(VideoInfo),
+}
+
+/// Path and extra-args override for a single external executable, mirroring
+/// hoshinova's `executable_path`/`args` downloader settings.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ToolSettings {
+    /// Executable path or name; falls back to the bare command when unset.
+    pub executable_path: Option<String>,
+    /// Extra global arguments prepended to every invocation.
+    pub args: Vec<String>,
+}
+
+/// External-tool configuration, loaded from an optional config file so users
+/// can point at custom `ffmpeg`/`ffprobe`/`yt-dlp` builds and inject flags.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ToolConfig {
+    pub ffmpeg: ToolSettings,
+    pub ffprobe: ToolSettings,
+    pub ytdlp: ToolSettings,
+}
+
+/// The set of encoders and filters an installed FFmpeg actually supports, so
+/// the frontend can validate codec/filter choices before a conversion starts.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct FfmpegCapabilities {
+    pub version: String,
+    pub video_encoders: Vec<String>,
+    pub audio_encoders: Vec<String>,
+    pub filters: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct Progress {
     pub percent: f64,
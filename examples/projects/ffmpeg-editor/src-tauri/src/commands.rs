@@ -1,413 +1,1485 @@
-use tauri::{AppHandle, Emitter};
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::Command;
-use std::process::Stdio;
-use std::sync::atomic::{AtomicBool, Ordering};
-
-use crate::models::{ConvertOptions, MediaInfo, Progress};
-use crate::utils::{parse_duration, parse_progress};
-use crate::ffmpeg::build_filter_chain;
-
-// Global flag to track cancellation - Re-declared here effectively as a new static for this module?
-// No, generics/statics don't work like that across mods easily if we want "THE" global.
-// Usage in lib.rs was `static CANCELLED`.
-// If we move it here, it is local to this module.
-// Since `cancel_conversion` is also moved here, it should share the same static.
-static CANCELLED: AtomicBool = AtomicBool::new(false);
-
-/// Get media info using ffprobe
-#[tauri::command]
-pub async fn get_media_info(path: String) -> Result<MediaInfo, String> {
-    let output = tokio::time::timeout(
-        std::time::Duration::from_secs(10),
-        Command::new("ffprobe")
-            .args([
-                "-v", "error",
-                "-show_entries", "format=duration,bit_rate:stream=width,height,codec_name,codec_type",
-                "-of", "json",
-                &path,
-            ])
-            .output(),
-    )
-    .await
-    .map_err(|_| "ffprobe timed out".to_string())?
-    .map_err(|e| format!("Failed to run ffprobe: {}", e))?;
-
-    if !output.status.success() {
-        return Err("ffprobe failed".to_string());
-    }
-
-    let json_str = String::from_utf8_lossy(&output.stdout);
-    let json: serde_json::Value =
-        serde_json::from_str(&json_str).map_err(|e| format!("Failed to parse JSON: {}", e))?;
-
-    let duration = json["format"]["duration"]
-        .as_str()
-        .and_then(|s| s.parse::<f64>().ok())
-        .unwrap_or(0.0);
-
-    let bitrate = json["format"]["bit_rate"]
-        .as_str()
-        .and_then(|s| s.parse::<u64>().ok());
-
-    let streams = json["streams"].as_array();
-    let mut width = None;
-    let mut height = None;
-    let mut codec = None;
-    let mut audio_codec = None;
-
-    if let Some(streams) = streams {
-        for stream in streams {
-            let codec_type = stream["codec_type"].as_str().unwrap_or("");
-            if codec_type == "video" {
-                width = stream["width"].as_u64().map(|w| w as u32);
-                height = stream["height"].as_u64().map(|h| h as u32);
-                codec = stream["codec_name"].as_str().map(|s| s.to_string());
-            } else if codec_type == "audio" {
-                audio_codec = stream["codec_name"].as_str().map(|s| s.to_string());
-            }
-        }
-    }
-
-    Ok(MediaInfo {
-        duration,
-        width,
-        height,
-        codec,
-        audio_codec,
-        format: Some(
-            path.split('.')
-                .last()
-                .unwrap_or("unknown")
-                .to_uppercase(),
-        ),
-        bitrate,
-    })
-}
-
-/// Convert/process media using ffmpeg
-#[tauri::command]
-pub async fn convert_media(app: AppHandle, options: ConvertOptions) -> Result<(), String> {
-    CANCELLED.store(false, Ordering::SeqCst);
-
-    // First, get duration for progress calculation
-    let probe_output = tokio::time::timeout(
-        std::time::Duration::from_secs(10),
-        Command::new("ffprobe")
-            .args(["-v", "error", "-show_format", &options.input])
-            .output(),
-    )
-    .await
-    .map_err(|_| "ffprobe (probe) timed out".to_string())?
-    .map_err(|e| format!("Failed to probe: {}", e))?;
-
-    let probe_str = String::from_utf8_lossy(&probe_output.stdout);
-    let total_duration = parse_duration(&probe_str);
-
-    // Build ffmpeg command
-    let mut args = Vec::new();
-
-    // Hardware Acceleration
-    if options.hw_accel.unwrap_or(false) {
-        args.push("-hwaccel".to_string());
-        args.push("auto".to_string());
-    }
-
-    args.push("-y".to_string());
-    args.push("-i".to_string());
-    args.push(options.input.clone());
-
-    // Image Overlay Input (Index 1)
-    if let Some(ref overlays) = options.overlays {
-        if let Some(ref img) = overlays.image {
-            args.push("-i".to_string());
-            args.push(img.path.clone());
-        }
-    }
-
-    // Add trim options
-    if let Some(start) = options.start_time {
-        args.push("-ss".to_string());
-        args.push(format!("{:.2}", start));
-    }
-    if let Some(end) = options.end_time {
-        args.push("-to".to_string());
-        args.push(format!("{:.2}", end));
-    }
-
-    // Audio only extraction
-    if options.audio_only {
-        args.push("-vn".to_string());
-    }
-
-    // Video codec
-    if let Some(ref vcodec) = options.video_codec {
-        args.push("-c:v".to_string());
-        args.push(vcodec.clone());
-    }
-
-    // Audio codec
-    if let Some(ref acodec) = options.audio_codec {
-        args.push("-c:a".to_string());
-        args.push(acodec.clone());
-    }
-
-    // Quality (CRF)
-    if let Some(crf) = options.crf {
-        args.push("-crf".to_string());
-        args.push(crf.to_string());
-    }
-
-    // Preset
-    if let Some(ref preset) = options.preset {
-        args.push("-preset".to_string());
-        args.push(preset.clone());
-    }
-
-    // Construct arguments using helper
-    let (filter_args, _has_complex) = build_filter_chain(&options, total_duration);
-    args.extend(filter_args);
-
-    // Progress
-    args.push("-progress".to_string());
-    args.push("pipe:1".to_string());
-
-    // Output
-    args.push(options.output.clone());
-
-    // Spawn
-    let mut child = Command::new("ffmpeg")
-        .args(&args)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to start ffmpeg: {}", e))?;
-
-    let stderr = child.stderr.take().unwrap();
-    let mut reader = BufReader::new(stderr).lines();
-
-    while let Ok(Some(line)) = reader.next_line().await {
-        if CANCELLED.load(Ordering::SeqCst) {
-            let _ = child.kill().await;
-            return Err("Cancelled by user".to_string());
-        }
-        if let Some(progress) = parse_progress(&line, total_duration) {
-            let _ = app.emit("ffmpeg-progress", progress);
-        }
-    }
-
-    let status = child.wait().await.map_err(|e| format!("Process error: {}", e))?;
-
-    if !status.success() {
-        return Err("FFmpeg conversion failed".to_string());
-    }
-
-    let _ = app.emit(
-        "ffmpeg-progress",
-        Progress {
-            percent: 100.0,
-            time: total_duration,
-            speed: "Done".to_string(),
-            size: "Complete".to_string(),
-        },
-    );
-
-    Ok(())
-}
-
-#[tauri::command]
-pub fn cancel_conversion() {
-    CANCELLED.store(true, Ordering::SeqCst);
-}
-
-#[tauri::command]
-pub async fn merge_media(app: AppHandle, files: Vec<String>, output: String) -> Result<(), String> {
-    CANCELLED.store(false, Ordering::SeqCst);
-    
-    let temp_dir = std::env::temp_dir();
-    let list_path = temp_dir.join(format!("ffmpeg_concat_{}.txt", uuid::Uuid::new_v4()));
-    
-    let mut content = String::new();
-    for file in files {
-        content.push_str(&format!("file '{}'\n", file.replace("'", "'\\''")));
-    }
-    
-    std::fs::write(&list_path, content).map_err(|e| format!("Failed to create concat list: {}", e))?;
-    
-    let args = vec![
-        "-f".to_string(), "concat".to_string(),
-        "-safe".to_string(), "0".to_string(),
-        "-i".to_string(), list_path.to_str().unwrap().to_string(),
-        "-c".to_string(), "copy".to_string(),
-        "-y".to_string(),
-        output.clone()
-    ];
-
-    let mut child = Command::new("ffmpeg")
-        .args(&args)
-        .spawn()
-        .map_err(|e| format!("Failed to start ffmpeg merge: {}", e))?;
-
-    let status = child.wait().await.map_err(|e| format!("Process error during merge: {}", e))?;
-    let _ = std::fs::remove_file(list_path);
-
-    if !status.success() {
-        return Err("FFmpeg merge failed".to_string());
-    }
-
-    let _ = app.emit("ffmpeg-progress", Progress {
-        percent: 100.0,
-        time: 0.0,
-        speed: "Done".to_string(),
-        size: "Complete".to_string(),
-    });
-
-    Ok(())
-}
-
-#[tauri::command]
-pub async fn check_ffmpeg() -> Result<String, String> {
-    let output = Command::new("ffmpeg")
-        .args(["-version"])
-        .output()
-        .await
-        .map_err(|_| "FFmpeg not found. Please install FFmpeg.".to_string())?;
-
-    let version = String::from_utf8_lossy(&output.stdout);
-    let first_line = version.lines().next().unwrap_or("Unknown version");
-    Ok(first_line.to_string())
-}
-
-#[tauri::command]
-pub async fn download_video(app: AppHandle, url: String, output_dir: String) -> Result<String, String> {
-    CANCELLED.store(false, Ordering::SeqCst);
-    
-    // Check if yt-dlp is available
-    let _ = Command::new("yt-dlp")
-        .arg("--version")
-        .output()
-        .await
-        .map_err(|_| "yt-dlp not found. Please install yt-dlp.".to_string())?;
-
-    let mut child = Command::new("yt-dlp")
-        .args([
-            "-o", &format!("{}\\%(title)s.%(ext)s", output_dir),
-            "--newline",
-            &url
-        ])
-        .stdout(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to start yt-dlp: {}", e))?;
-
-    let stdout = child.stdout.take().unwrap();
-    let mut reader = BufReader::new(stdout).lines();
-    let mut last_filename = String::new();
-
-    while let Ok(Some(line)) = reader.next_line().await {
-        if CANCELLED.load(Ordering::SeqCst) {
-             let _ = child.kill().await;
-             return Err("Cancelled by user".to_string());
-        }
-
-        if line.starts_with("[download]") {
-             let re = regex::Regex::new(r"(\d+\.?\d*)%").unwrap();
-             if let Some(caps) = re.captures(&line) {
-                 if let Ok(percent) = caps[1].parse::<f64>() {
-                      let _ = app.emit("download-progress", Progress {
-                        percent,
-                        time: 0.0,
-                        speed: "".to_string(), 
-                        size: "".to_string(),
-                    });
-                 }
-             }
-             
-             if line.contains("Destination: ") {
-                 if let Some(path) = line.split("Destination: ").nth(1) {
-                     last_filename = path.trim().to_string();
-                 }
-             } else if line.contains("has already been downloaded") {
-                  if let Some(path) = line.split("] ").nth(1).and_then(|s| s.split(" has").next()) {
-                      last_filename = path.trim().to_string();
-                  }
-             }
-             
-             if line.contains("Merging formats into") {
-                 // Logic to handle "Merging formats into "path/to/file""
-                 if let Some(path_part) = line.split("into \"").nth(1) {
-                     if let Some(path) = path_part.split("\"").next() {
-                         last_filename = path.trim().to_string();
-                     }
-                 }
-             }
-        }
-    }
-
-    let status = child.wait().await.map_err(|e| format!("yt-dlp process error: {}", e))?;
-
-    if !status.success() {
-        return Err("Download failed".to_string());
-    }
-
-    // Attempt to find the file if last_filename is empty or partial
-    if last_filename.is_empty() {
-        return Ok("Download complete (check output folder)".to_string());
-    }
-
-    Ok(last_filename)
-}
-
-/// Generate a preview frame for the current settings
-#[tauri::command]
-pub async fn generate_preview(_app: AppHandle, options: ConvertOptions, timestamp: f64) -> Result<String, String> {
-    
-    // Build ffmpeg command
-    let mut args = Vec::new();
-
-    args.push("-y".to_string());
-    args.push("-ss".to_string());
-    args.push(format!("{:.3}", timestamp));
-    
-    args.push("-i".to_string());
-    args.push(options.input.clone());
-
-    // Image Overlay Input
-    if let Some(ref overlays) = options.overlays {
-        if let Some(ref img) = overlays.image {
-            args.push("-i".to_string());
-            args.push(img.path.clone());
-        }
-    }
-
-    // Filter Chain
-    let (filter_args, _) = build_filter_chain(&options, 0.0); // Duration doesn't matter for single frame preview except for fade, which uses options.end_time or duration calc 
-    // Optimization: Exclude audio filters for image preview
-    let filter_args_video: Vec<String> = filter_args.into_iter().filter(|a| !a.contains("afade") && !a.contains("atempo") && !a.contains("volume") && !a.contains("equalizer") && !a.contains("acompressor") && !a.contains("loudnorm") && !a.contains("afftdn") && !a.eq("-af") && !a.eq("-map") && !a.eq("0:a")).collect();
-    
-    args.extend(filter_args_video);
-
-    args.push("-vframes".to_string());
-    args.push("1".to_string());
-    
-    // Output format: JPEG pipe
-    args.push("-f".to_string());
-    args.push("image2".to_string());
-    args.push("-".to_string()); // Output to stdout
-
-    let mut cmd = Command::new("ffmpeg");
-    cmd.args(&args);
-    
-    // Tauri's Command::output() returns a Result<Output, CommandError>
-    // We need to map errors correctly
-    let output = cmd.output().await.map_err(|e| format!("Failed to run ffmpeg preview: {}", e))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("FFmpeg preview failed: {}", stderr));
-    }
-
-    // Convert to base64
-    use base64::{Engine as _, engine::general_purpose};
-    let b64 = general_purpose::STANDARD.encode(&output.stdout);
-    Ok(format!("data:image/jpeg;base64,{}", b64))
-}
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::models::{
+    ConvertOptions, FfmpegCapabilities, MediaChapter, MediaFormat, MediaInfo, MediaStream,
+    PlaylistInfo, Progress, Rational, Rendition, StreamProps, VideoFormat, VideoInfo,
+    YoutubeDlOutput,
+};
+use crate::utils::{parse_duration, parse_progress_pass};
+use crate::ffmpeg::{
+    build_filter_chain, build_segment_args, build_timeline_filter, hw_encoder, hw_init_args,
+    resolve_codecs, speed_ramp_output_duration, speed_ramp_unsupported,
+};
+use crate::config::{ffmpeg_command, ffprobe_command, ytdlp_command};
+
+// Global flag to track cancellation - Re-declared here effectively as a new static for this module?
+// No, generics/statics don't work like that across mods easily if we want "THE" global.
+// Usage in lib.rs was `static CANCELLED`.
+// If we move it here, it is local to this module.
+// Since `cancel_conversion` is also moved here, it should share the same static.
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Get media info using ffprobe.
+///
+/// Runs a full JSON probe (`-show_format -show_streams -show_chapters`) and
+/// deserializes it into a structured [`MediaInfo`], so the frontend can list
+/// every track and pick which audio/subtitle stream to map.
+#[tauri::command]
+pub async fn get_media_info(path: String) -> Result<MediaInfo, String> {
+    let output = tokio::time::timeout(
+        std::time::Duration::from_secs(10),
+        ffprobe_command()
+            .args([
+                "-v", "quiet",
+                "-print_format", "json",
+                "-show_format",
+                "-show_streams",
+                "-show_chapters",
+                &path,
+            ])
+            .output(),
+    )
+    .await
+    .map_err(|_| "ffprobe timed out".to_string())?
+    .map_err(|e| format!("Failed to run ffprobe: {}", e))?;
+
+    if !output.status.success() {
+        return Err("ffprobe failed".to_string());
+    }
+
+    let json_str = String::from_utf8_lossy(&output.stdout);
+    let json: serde_json::Value =
+        serde_json::from_str(&json_str).map_err(|e| format!("Failed to parse JSON: {}", e))?;
+
+    let format = parse_format(&json["format"]);
+    let streams = json["streams"]
+        .as_array()
+        .map(|s| s.iter().map(parse_stream).collect())
+        .unwrap_or_default();
+    let chapters = json["chapters"]
+        .as_array()
+        .map(|c| c.iter().map(parse_chapter).collect())
+        .unwrap_or_default();
+
+    Ok(MediaInfo {
+        format,
+        streams,
+        chapters,
+    })
+}
+
+/// Read a string-or-number JSON field as a parseable numeric value.
+fn parse_num_field<T: std::str::FromStr>(value: &serde_json::Value) -> Option<T> {
+    value
+        .as_str()
+        .and_then(|s| s.parse().ok())
+        .or_else(|| value.as_f64().and_then(|n| n.to_string().parse().ok()))
+}
+
+fn parse_format(format: &serde_json::Value) -> MediaFormat {
+    let tags = format["tags"]
+        .as_object()
+        .map(|obj| {
+            obj.iter()
+                .filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    MediaFormat {
+        format_name: format["format_name"].as_str().map(|s| s.to_string()),
+        duration: parse_num_field(&format["duration"]).unwrap_or(0.0),
+        bitrate: parse_num_field(&format["bit_rate"]),
+        size: parse_num_field(&format["size"]),
+        tags,
+    }
+}
+
+fn parse_stream(stream: &serde_json::Value) -> MediaStream {
+    let codec_type = stream["codec_type"].as_str().unwrap_or("").to_string();
+    let props = match codec_type.as_str() {
+        "video" => StreamProps::Video {
+            width: stream["width"].as_u64().map(|w| w as u32),
+            height: stream["height"].as_u64().map(|h| h as u32),
+            pix_fmt: stream["pix_fmt"].as_str().map(|s| s.to_string()),
+            avg_frame_rate: stream["avg_frame_rate"]
+                .as_str()
+                .and_then(Rational::parse),
+            rotation: parse_rotation(stream),
+        },
+        "audio" => StreamProps::Audio {
+            sample_rate: parse_num_field(&stream["sample_rate"]),
+            channels: stream["channels"].as_u64().map(|c| c as u32),
+            channel_layout: stream["channel_layout"].as_str().map(|s| s.to_string()),
+        },
+        "subtitle" => StreamProps::Subtitle {
+            language: stream["tags"]["language"].as_str().map(|s| s.to_string()),
+        },
+        _ => StreamProps::Other,
+    };
+
+    MediaStream {
+        index: stream["index"].as_u64().unwrap_or(0) as u32,
+        codec_type,
+        codec_name: stream["codec_name"].as_str().map(|s| s.to_string()),
+        props,
+    }
+}
+
+/// Extract display rotation (degrees) from a video stream's side-data list.
+fn parse_rotation(stream: &serde_json::Value) -> i32 {
+    stream["side_data_list"]
+        .as_array()
+        .and_then(|list| {
+            list.iter()
+                .find_map(|sd| sd["rotation"].as_i64().map(|r| r as i32))
+        })
+        .unwrap_or(0)
+}
+
+fn parse_chapter(chapter: &serde_json::Value) -> MediaChapter {
+    MediaChapter {
+        start: parse_num_field(&chapter["start_time"]).unwrap_or(0.0),
+        end: parse_num_field(&chapter["end_time"]).unwrap_or(0.0),
+        title: chapter["tags"]["title"].as_str().map(|s| s.to_string()),
+    }
+}
+
+/// Best-effort probe of the source's first video/audio codec names, used to
+/// decide whether a track can be stream-copied. Returns `(None, None)` if the
+/// probe fails for any reason.
+async fn probe_source_codecs(input: &str) -> (Option<String>, Option<String>) {
+    let output = ffprobe_command()
+        .args([
+            "-v", "error",
+            "-show_entries", "stream=codec_type,codec_name",
+            "-of", "json",
+            input,
+        ])
+        .output()
+        .await;
+
+    let Ok(output) = output else {
+        return (None, None);
+    };
+    let json_str = String::from_utf8_lossy(&output.stdout);
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(&json_str) else {
+        return (None, None);
+    };
+
+    let mut video = None;
+    let mut audio = None;
+    if let Some(streams) = json["streams"].as_array() {
+        for stream in streams {
+            match stream["codec_type"].as_str() {
+                Some("video") if video.is_none() => {
+                    video = stream["codec_name"].as_str().map(|s| s.to_string());
+                }
+                Some("audio") if audio.is_none() => {
+                    audio = stream["codec_name"].as_str().map(|s| s.to_string());
+                }
+                _ => {}
+            }
+        }
+    }
+    (video, audio)
+}
+
+/// Run a single ffmpeg invocation, streaming scaled progress for its pass.
+///
+/// `pass`/`total_passes` scale the emitted percent so a two-pass encode reports
+/// a continuous 0–100% across both runs. Honors the global cancellation flag.
+async fn run_ffmpeg_pass(
+    app: &AppHandle,
+    args: &[String],
+    duration: f64,
+    pass: u32,
+    total_passes: u32,
+) -> Result<(), String> {
+    let mut child = ffmpeg_command()
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start ffmpeg: {}", e))?;
+
+    let stderr = child.stderr.take().unwrap();
+    let mut reader = BufReader::new(stderr).lines();
+
+    while let Ok(Some(line)) = reader.next_line().await {
+        if CANCELLED.load(Ordering::SeqCst) {
+            let _ = child.kill().await;
+            return Err("Cancelled by user".to_string());
+        }
+        if let Some(progress) = parse_progress_pass(&line, duration, pass, total_passes) {
+            let _ = app.emit("ffmpeg-progress", progress);
+        }
+    }
+
+    let status = child.wait().await.map_err(|e| format!("Process error: {}", e))?;
+    if !status.success() {
+        return Err("FFmpeg conversion failed".to_string());
+    }
+    Ok(())
+}
+
+/// Convert/process media using ffmpeg
+#[tauri::command]
+pub async fn convert_media(app: AppHandle, options: ConvertOptions) -> Result<(), String> {
+    CANCELLED.store(false, Ordering::SeqCst);
+
+    // First, get duration for progress calculation
+    let probe_output = tokio::time::timeout(
+        std::time::Duration::from_secs(10),
+        ffprobe_command()
+            .args(["-v", "error", "-show_format", &options.input])
+            .output(),
+    )
+    .await
+    .map_err(|_| "ffprobe (probe) timed out".to_string())?
+    .map_err(|e| format!("Failed to probe: {}", e))?;
+
+    let probe_str = String::from_utf8_lossy(&probe_output.stdout);
+    let total_duration = parse_duration(&probe_str);
+
+    // Target-VMAF mode auto-selects a CRF before the real encode begins.
+    let mut options = options;
+
+    // Hardware acceleration is best-effort: if the requested backend isn't
+    // present on this host, drop it so every downstream consumer (init args,
+    // encoder swap, GPU-resident filters) falls back to the software chain.
+    if let Some(ref hw) = options.hw_accel {
+        if !hw_backend_available(&hw.backend).await {
+            let _ = app.emit(
+                "ffmpeg-progress",
+                Progress {
+                    percent: 0.0,
+                    time: 0.0,
+                    speed: format!("{} unavailable, using software", hw.backend),
+                    size: "hwaccel".to_string(),
+                },
+            );
+            options.hw_accel = None;
+        }
+    }
+
+    if options.target_vmaf.is_some() {
+        let crf = search_target_vmaf(&app, &options, total_duration).await?;
+        options.crf = Some(crf);
+    }
+
+    // Scene-split parallel encoding takes a separate chunk-and-concat path.
+    if options.scene_split {
+        return convert_media_parallel(app, options, total_duration).await;
+    }
+
+    // --- Input + filter prefix (everything up to the encode settings) ---
+    let mut input_args = Vec::new();
+
+    // Hardware Acceleration (decode/init args must precede -i)
+    if let Some(ref hw) = options.hw_accel {
+        input_args.extend(hw_init_args(hw));
+    }
+
+    input_args.push("-y".to_string());
+    input_args.push("-i".to_string());
+    input_args.push(options.input.clone());
+
+    // Image Overlay Input (Index 1)
+    if let Some(ref overlays) = options.overlays {
+        if let Some(ref img) = overlays.image {
+            input_args.push("-i".to_string());
+            input_args.push(img.path.clone());
+        }
+    }
+
+    // The declarative timeline does its own trimming inside filter_complex, so
+    // skip the plain -ss/-to trims when it is active.
+    let timeline_filter = options
+        .timeline
+        .as_ref()
+        .map(|t| build_timeline_filter(t, total_duration))
+        .filter(|(_, complex)| *complex);
+
+    // The speed-ramp filtergraph also trims inside filter_complex.
+    let speed_ramp = !options.fast.is_empty() || !options.slow.is_empty();
+
+    // Add trim options
+    if timeline_filter.is_none() && !speed_ramp {
+        if let Some(start) = options.start_time {
+            input_args.push("-ss".to_string());
+            input_args.push(format!("{:.2}", start));
+        }
+        if let Some(end) = options.end_time {
+            input_args.push("-to".to_string());
+            input_args.push(format!("{:.2}", end));
+        }
+    }
+
+    // Audio only extraction
+    if options.audio_only {
+        input_args.push("-vn".to_string());
+    }
+
+    // Construct filter arguments: the timeline builder takes precedence over
+    // the standard per-filter chain when present.
+    if let Some((timeline_args, _)) = timeline_filter {
+        input_args.extend(timeline_args);
+    } else {
+        // The speed-ramp filtergraph only re-applies color/scale after its
+        // concat, so reject filters it would otherwise silently drop.
+        if let Some(feature) = speed_ramp_unsupported(&options) {
+            return Err(format!(
+                "{} cannot be combined with variable-speed (fast/slow) ranges",
+                feature
+            ));
+        }
+        let (filter_args, _has_complex) = build_filter_chain(&options, total_duration);
+        input_args.extend(filter_args);
+    }
+
+    // --- Encode settings ---
+    // Resolve codecs against the output container: fill defaults, reject
+    // incompatible pairings, and stream-copy when nothing needs re-encoding.
+    let (source_video, source_audio) = probe_source_codecs(&options.input).await;
+    let ext = std::path::Path::new(&options.output)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_string();
+    let plan = resolve_codecs(&options, &ext, source_video.as_deref(), source_audio.as_deref())?;
+
+    let mut encode_args = Vec::new();
+
+    // Video codec (copy, or the requested/default encoder with hw swap).
+    if !options.audio_only {
+        if plan.copy_video {
+            encode_args.push("-c:v".to_string());
+            encode_args.push("copy".to_string());
+        } else if let Some(ref vcodec) = plan.video_codec {
+            let chosen = options
+                .hw_accel
+                .as_ref()
+                .and_then(|hw| hw_encoder(&hw.backend, vcodec))
+                .unwrap_or_else(|| vcodec.clone());
+            encode_args.push("-c:v".to_string());
+            encode_args.push(chosen);
+        }
+    }
+
+    // Audio codec (copy or the requested/default encoder).
+    if plan.copy_audio {
+        encode_args.push("-c:a".to_string());
+        encode_args.push("copy".to_string());
+    } else if let Some(ref acodec) = plan.audio_codec {
+        encode_args.push("-c:a".to_string());
+        encode_args.push(acodec.clone());
+    }
+
+    // Rate-control options only apply when the video is actually re-encoded.
+    if !plan.copy_video {
+        // Quality (CRF)
+        if let Some(crf) = options.crf {
+            encode_args.push("-crf".to_string());
+            encode_args.push(crf.to_string());
+        }
+
+        // Target bitrate + rate control.
+        if let Some(bitrate) = options.bitrate {
+            encode_args.push("-b:v".to_string());
+            encode_args.push(bitrate.to_string());
+        }
+        if let Some(max_bitrate) = options.max_bitrate {
+            encode_args.push("-maxrate".to_string());
+            encode_args.push(max_bitrate.to_string());
+        }
+        if let Some(bufsize) = options.bufsize {
+            encode_args.push("-bufsize".to_string());
+            encode_args.push(bufsize.to_string());
+        }
+
+        // Preset
+        if let Some(ref preset) = options.preset {
+            encode_args.push("-preset".to_string());
+            encode_args.push(preset.clone());
+        }
+    }
+
+    // --- Optional preprocess: render a near-lossless intermediate once so the
+    // real encode(s) reuse it instead of re-running an expensive filter chain.
+    let mut intermediate_file: Option<std::path::PathBuf> = None;
+    if options.preprocess {
+        let intermediate =
+            std::env::temp_dir().join(format!("ffmpeg_pre_{}.mkv", uuid::Uuid::new_v4()));
+        let mut pre_args = input_args.clone();
+        pre_args.push("-c:v".to_string());
+        pre_args.push("ffv1".to_string());
+        pre_args.push("-c:a".to_string());
+        pre_args.push("pcm_s16le".to_string());
+        pre_args.push("-progress".to_string());
+        pre_args.push("pipe:1".to_string());
+        pre_args.push(intermediate.to_string_lossy().to_string());
+        run_ffmpeg_pass(&app, &pre_args, total_duration, 1, 1).await?;
+
+        // Subsequent passes read the intermediate directly, no filters/hwaccel.
+        input_args = vec![
+            "-y".to_string(),
+            "-i".to_string(),
+            intermediate.to_string_lossy().to_string(),
+        ];
+        intermediate_file = Some(intermediate);
+    }
+
+    // --- Output args: single file or a segmented playlist directory. ---
+    let output_args = if let Some(ref segment) = options.segment {
+        let output_dir = std::path::PathBuf::from(&options.output);
+        std::fs::create_dir_all(&output_dir)
+            .map_err(|e| format!("Failed to create output directory: {}", e))?;
+        let (seg_args, playlist) = build_segment_args(segment, &output_dir);
+        let mut out = seg_args;
+        out.push(playlist);
+        out
+    } else {
+        vec![options.output.clone()]
+    };
+
+    // Speed-ramping compresses the timeline, so the encode emits fewer seconds
+    // than the source; scale progress against the remapped duration.
+    let progress_duration =
+        speed_ramp_output_duration(&options, total_duration).unwrap_or(total_duration);
+
+    // --- Run the encode: two-pass or single (copy can't be two-passed). ---
+    let result = if options.two_pass && !plan.copy_video {
+        let passlog = std::env::temp_dir()
+            .join(format!("ffmpeg2pass_{}", uuid::Uuid::new_v4()))
+            .to_string_lossy()
+            .to_string();
+        let null_sink = if cfg!(windows) { "NUL" } else { "/dev/null" };
+
+        // Pass 1: analysis only, output discarded.
+        let mut pass1 = input_args.clone();
+        pass1.extend(encode_args.clone());
+        pass1.push("-pass".to_string());
+        pass1.push("1".to_string());
+        pass1.push("-passlogfile".to_string());
+        pass1.push(passlog.clone());
+        pass1.push("-an".to_string());
+        pass1.push("-f".to_string());
+        pass1.push("null".to_string());
+        pass1.push(null_sink.to_string());
+        run_ffmpeg_pass(&app, &pass1, progress_duration, 1, 2).await?;
+
+        // Pass 2: real output reusing the first-pass statistics.
+        let mut pass2 = input_args.clone();
+        pass2.extend(encode_args.clone());
+        pass2.push("-pass".to_string());
+        pass2.push("2".to_string());
+        pass2.push("-passlogfile".to_string());
+        pass2.push(passlog.clone());
+        pass2.push("-progress".to_string());
+        pass2.push("pipe:1".to_string());
+        pass2.extend(output_args.clone());
+        let r = run_ffmpeg_pass(&app, &pass2, progress_duration, 2, 2).await;
+
+        let _ = std::fs::remove_file(format!("{}-0.log", passlog));
+        let _ = std::fs::remove_file(format!("{}-0.log.mbtree", passlog));
+        r
+    } else {
+        let mut single = input_args.clone();
+        single.extend(encode_args.clone());
+        single.push("-progress".to_string());
+        single.push("pipe:1".to_string());
+        single.extend(output_args.clone());
+        run_ffmpeg_pass(&app, &single, progress_duration, 1, 1).await
+    };
+
+    if let Some(path) = intermediate_file {
+        let _ = std::fs::remove_file(path);
+    }
+    result?;
+
+    let _ = app.emit(
+        "ffmpeg-progress",
+        Progress {
+            percent: 100.0,
+            time: total_duration,
+            speed: "Done".to_string(),
+            size: "Complete".to_string(),
+        },
+    );
+
+    Ok(())
+}
+
+/// Whether the installed ffmpeg advertises the given hardware backend in its
+/// `-hwaccels` list. The backend names used by `HwAccel` map onto the ffmpeg
+/// method names (`nvenc` is driven through `cuda`).
+async fn hw_backend_available(backend: &str) -> bool {
+    let method = match backend {
+        "nvenc" => "cuda",
+        other => other,
+    };
+    ffmpeg_command()
+        .args(["-hide_banner", "-hwaccels"])
+        .output()
+        .await
+        .map(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .lines()
+                .any(|line| line.trim() == method)
+        })
+        .unwrap_or(false)
+}
+
+/// Whether the installed ffmpeg was built with the `libvmaf` filter.
+async fn has_libvmaf() -> bool {
+    ffmpeg_command()
+        .args(["-hide_banner", "-filters"])
+        .output()
+        .await
+        .map(|o| String::from_utf8_lossy(&o.stdout).contains("libvmaf"))
+        .unwrap_or(false)
+}
+
+/// Compute the mean VMAF of `distorted` against `reference`.
+async fn run_vmaf(distorted: &str, reference: &str) -> Result<f64, String> {
+    let output = ffmpeg_command()
+        .args([
+            "-hide_banner",
+            "-i", distorted,
+            "-i", reference,
+            "-lavfi", "libvmaf",
+            "-f", "null",
+            "-",
+        ])
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run libvmaf: {}", e))?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let re = regex::Regex::new(r"VMAF score: ([0-9.]+)").unwrap();
+    re.captures(&stderr)
+        .and_then(|c| c[1].parse::<f64>().ok())
+        .ok_or_else(|| "Could not parse VMAF score".to_string())
+}
+
+/// Encode each reference clip at `crf` with `vcodec` and return the mean VMAF
+/// across them. The encoder must match the one the real encode will use, since
+/// CRF has a different perceptual meaning per codec family.
+async fn measure_vmaf_at_crf(
+    vcodec: &str,
+    reference_clips: &[String],
+    crf: u8,
+) -> Result<f64, String> {
+    let mut scores = Vec::new();
+    for (i, reference) in reference_clips.iter().enumerate() {
+        let distorted = format!("{}.crf{}.{}.mp4", reference, crf, i);
+        run_cancellable(vec![
+            "-y".to_string(),
+            "-i".to_string(),
+            reference.clone(),
+            "-c:v".to_string(),
+            vcodec.to_string(),
+            "-crf".to_string(),
+            crf.to_string(),
+            "-an".to_string(),
+            distorted.clone(),
+        ])
+        .await?;
+        let score = run_vmaf(&distorted, reference).await;
+        let _ = std::fs::remove_file(&distorted);
+        scores.push(score?);
+    }
+    if scores.is_empty() {
+        return Err("No samples to measure".to_string());
+    }
+    Ok(scores.iter().sum::<f64>() / scores.len() as f64)
+}
+
+/// Binary-search CRF (18–40) over short samples to hit the target VMAF, then
+/// return the highest CRF whose score stays at or above the target. Uses a
+/// probe budget of ~4 iterations with early exit when a score is at most 0.5
+/// above the target.
+async fn search_target_vmaf(
+    app: &AppHandle,
+    options: &ConvertOptions,
+    total_duration: f64,
+) -> Result<u8, String> {
+    let target = options.target_vmaf.unwrap_or(93.0) as f64;
+
+    if !has_libvmaf().await {
+        return Err("This ffmpeg build lacks the libvmaf filter required for target-VMAF mode".to_string());
+    }
+
+    // Resolve the video encoder the same way the real encode does, so the CRF
+    // search calibrates on the exact codec that will produce the output (e.g.
+    // libsvtav1 for .webm, mpeg4 for .avi) rather than always on libx264.
+    let (source_video, source_audio) = probe_source_codecs(&options.input).await;
+    let ext = std::path::Path::new(&options.output)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_string();
+    let plan = resolve_codecs(options, &ext, source_video.as_deref(), source_audio.as_deref())?;
+    let vcodec = plan
+        .video_codec
+        .unwrap_or_else(|| "libx264".to_string());
+
+    // Extract ~2s lossless reference samples around 25%/50%/75%.
+    let temp_dir = std::env::temp_dir();
+    let id = uuid::Uuid::new_v4();
+    let mut reference_clips = Vec::new();
+    for (i, frac) in [0.25, 0.5, 0.75].iter().enumerate() {
+        let pos = (total_duration * frac).max(0.0);
+        let clip = temp_dir
+            .join(format!("vmaf_ref_{}_{}.mkv", id, i))
+            .to_string_lossy()
+            .to_string();
+        run_cancellable(vec![
+            "-y".to_string(),
+            "-ss".to_string(),
+            format!("{:.3}", pos),
+            "-t".to_string(),
+            "2".to_string(),
+            "-i".to_string(),
+            options.input.clone(),
+            "-c:v".to_string(),
+            "ffv1".to_string(),
+            "-an".to_string(),
+            clip.clone(),
+        ])
+        .await?;
+        reference_clips.push(clip);
+    }
+
+    let (mut lo, mut hi) = (18i32, 40i32);
+    let mut chosen: Option<u8> = None;
+    for _ in 0..4 {
+        if lo > hi {
+            break;
+        }
+        let mid = ((lo + hi) / 2) as u8;
+        let score = measure_vmaf_at_crf(&vcodec, &reference_clips, mid).await?;
+
+        // Surface the search so the UI can show progress.
+        let _ = app.emit(
+            "ffmpeg-progress",
+            Progress {
+                percent: 0.0,
+                time: 0.0,
+                speed: format!("VMAF {:.1} @ CRF {}", score, mid),
+                size: "probing".to_string(),
+            },
+        );
+
+        if score >= target && score - target <= 0.5 {
+            chosen = Some(mid);
+            break;
+        }
+        if score >= target {
+            // Quality to spare: remember this CRF and try a higher one.
+            chosen = Some(mid);
+            lo = mid as i32 + 1;
+        } else {
+            // Below target: need more quality, so a lower CRF.
+            hi = mid as i32 - 1;
+        }
+    }
+
+    cleanup_files(&reference_clips);
+    Ok(chosen.unwrap_or_else(|| lo.clamp(18, 40) as u8))
+}
+
+/// Remove temporary files, ignoring errors.
+fn cleanup_files(files: &[String]) {
+    for file in files {
+        let _ = std::fs::remove_file(file);
+    }
+}
+
+/// Detect scene-change boundaries, returning cut times (seconds) within
+/// `[start, end]`. Falls back to an even split when detection finds nothing.
+async fn detect_scenes(input: &str, start: f64, end: f64, fallback_chunks: u32) -> Vec<f64> {
+    let output = ffmpeg_command()
+        .args([
+            "-hide_banner",
+            "-i", input,
+            "-filter:v", "select='gt(scene,0.3)',showinfo",
+            "-an",
+            "-f", "null",
+            "-",
+        ])
+        .output()
+        .await;
+
+    let mut cuts: Vec<f64> = Vec::new();
+    if let Ok(output) = output {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let re = regex::Regex::new(r"pts_time:([0-9.]+)").unwrap();
+        for caps in re.captures_iter(&stderr) {
+            if let Ok(t) = caps[1].parse::<f64>() {
+                if t > start && t < end {
+                    cuts.push(t);
+                }
+            }
+        }
+    }
+    cuts.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    cuts.dedup_by(|a, b| (*a - *b).abs() < 0.01);
+
+    // Fall back to an even split so we still parallelize when detection is empty.
+    if cuts.is_empty() && fallback_chunks > 1 && end > start {
+        let step = (end - start) / fallback_chunks as f64;
+        for i in 1..fallback_chunks {
+            cuts.push(start + step * i as f64);
+        }
+    }
+    cuts
+}
+
+/// Spawn an ffmpeg child and wait, killing it promptly if cancellation is
+/// requested. Output is discarded (per-chunk progress is aggregated elsewhere).
+async fn run_cancellable(args: Vec<String>) -> Result<(), String> {
+    let mut child = ffmpeg_command()
+        .args(&args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to start ffmpeg: {}", e))?;
+
+    loop {
+        if CANCELLED.load(Ordering::SeqCst) {
+            let _ = child.kill().await;
+            return Err("Cancelled by user".to_string());
+        }
+        tokio::select! {
+            status = child.wait() => {
+                let status = status.map_err(|e| format!("Process error: {}", e))?;
+                return if status.success() {
+                    Ok(())
+                } else {
+                    Err("FFmpeg chunk failed".to_string())
+                };
+            }
+            _ = tokio::time::sleep(std::time::Duration::from_millis(200)) => {}
+        }
+    }
+}
+
+/// Scene-split parallel encode: detect scene boundaries, encode each segment
+/// concurrently with identical codec params, then losslessly concat them.
+async fn convert_media_parallel(
+    app: AppHandle,
+    options: ConvertOptions,
+    total_duration: f64,
+) -> Result<(), String> {
+    let start = options.start_time.unwrap_or(0.0);
+    let end = options.end_time.unwrap_or(total_duration).max(start);
+    if end <= start {
+        return Err("Invalid time range for scene-split encoding".to_string());
+    }
+
+    let concurrency = options
+        .parallel
+        .unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get() as u32)
+                .unwrap_or(4)
+        })
+        .max(1);
+
+    // 1. Scene detection -> contiguous [from, to] segments.
+    let cuts = detect_scenes(&options.input, start, end, concurrency).await;
+    let mut boundaries = vec![start];
+    boundaries.extend(cuts);
+    boundaries.push(end);
+    let segments: Vec<(f64, f64)> = boundaries
+        .windows(2)
+        .filter(|w| w[1] > w[0])
+        .map(|w| (w[0], w[1]))
+        .collect();
+    if segments.is_empty() {
+        return Err("No segments to encode".to_string());
+    }
+
+    // 2. Identical encode params across all chunks (always re-encode, never
+    // stream-copy, so the concatenated output stays a valid stream).
+    let ext = std::path::Path::new(&options.output)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("mkv")
+        .to_string();
+    let plan = resolve_codecs(&options, &ext, None, None)?;
+    let mut encode_args = Vec::new();
+    if !options.audio_only {
+        if let Some(ref vc) = plan.video_codec {
+            let chosen = options
+                .hw_accel
+                .as_ref()
+                .and_then(|hw| hw_encoder(&hw.backend, vc))
+                .unwrap_or_else(|| vc.clone());
+            encode_args.push("-c:v".to_string());
+            encode_args.push(chosen);
+        }
+    }
+    if let Some(ref ac) = plan.audio_codec {
+        encode_args.push("-c:a".to_string());
+        encode_args.push(ac.clone());
+    }
+    if let Some(crf) = options.crf {
+        encode_args.push("-crf".to_string());
+        encode_args.push(crf.to_string());
+    }
+    if let Some(ref preset) = options.preset {
+        encode_args.push("-preset".to_string());
+        encode_args.push(preset.clone());
+    }
+
+    let (filter_args, _) = build_filter_chain(&options, total_duration);
+
+    // 3. Encode each segment to a temp file, bounded by `concurrency`.
+    let temp_dir = std::env::temp_dir();
+    let batch_id = uuid::Uuid::new_v4();
+    let seg_files: Vec<String> = (0..segments.len())
+        .map(|i| {
+            temp_dir
+                .join(format!("ffmpeg_chunk_{}_{:04}.{}", batch_id, i, ext))
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect();
+
+    let progress_done = Arc::new(Mutex::new(0.0_f64));
+    let mut index = 0usize;
+    while index < segments.len() {
+        if CANCELLED.load(Ordering::SeqCst) {
+            cleanup_files(&seg_files);
+            return Err("Cancelled by user".to_string());
+        }
+        let batch_end = (index + concurrency as usize).min(segments.len());
+        let mut handles = Vec::new();
+        for i in index..batch_end {
+            let (seg_start, seg_end) = segments[i];
+            let mut args = Vec::new();
+            if let Some(ref hw) = options.hw_accel {
+                args.extend(hw_init_args(hw));
+            }
+            args.push("-y".to_string());
+            args.push("-ss".to_string());
+            args.push(format!("{:.3}", seg_start));
+            args.push("-to".to_string());
+            args.push(format!("{:.3}", seg_end));
+            args.push("-i".to_string());
+            args.push(options.input.clone());
+            if let Some(ref overlays) = options.overlays {
+                if let Some(ref img) = overlays.image {
+                    args.push("-i".to_string());
+                    args.push(img.path.clone());
+                }
+            }
+            args.extend(filter_args.clone());
+            args.extend(encode_args.clone());
+            args.push(seg_files[i].clone());
+
+            let seg_duration = seg_end - seg_start;
+            let app = app.clone();
+            let progress_done = Arc::clone(&progress_done);
+            handles.push(tokio::spawn(async move {
+                let result = run_cancellable(args).await;
+                if result.is_ok() {
+                    let mut done = progress_done.lock().unwrap();
+                    *done += seg_duration;
+                    let percent = if total_duration > 0.0 {
+                        (*done / total_duration * 100.0).min(99.0)
+                    } else {
+                        0.0
+                    };
+                    let _ = app.emit(
+                        "ffmpeg-progress",
+                        Progress {
+                            percent,
+                            time: *done,
+                            speed: "chunk".to_string(),
+                            size: String::new(),
+                        },
+                    );
+                }
+                result
+            }));
+        }
+        for handle in handles {
+            match handle.await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => {
+                    cleanup_files(&seg_files);
+                    return Err(e);
+                }
+                Err(e) => {
+                    cleanup_files(&seg_files);
+                    return Err(format!("Task join error: {}", e));
+                }
+            }
+        }
+        index = batch_end;
+    }
+
+    // 4. Stitch the chunks losslessly via the concat demuxer.
+    let merge_result = merge_media(app.clone(), seg_files.clone(), options.output.clone()).await;
+    cleanup_files(&seg_files);
+    merge_result
+}
+
+#[tauri::command]
+pub fn cancel_conversion() {
+    CANCELLED.store(true, Ordering::SeqCst);
+}
+
+#[tauri::command]
+pub async fn merge_media(app: AppHandle, files: Vec<String>, output: String) -> Result<(), String> {
+    CANCELLED.store(false, Ordering::SeqCst);
+    
+    let temp_dir = std::env::temp_dir();
+    let list_path = temp_dir.join(format!("ffmpeg_concat_{}.txt", uuid::Uuid::new_v4()));
+    
+    let mut content = String::new();
+    for file in files {
+        content.push_str(&format!("file '{}'\n", file.replace("'", "'\\''")));
+    }
+    
+    std::fs::write(&list_path, content).map_err(|e| format!("Failed to create concat list: {}", e))?;
+    
+    let args = vec![
+        "-f".to_string(), "concat".to_string(),
+        "-safe".to_string(), "0".to_string(),
+        "-i".to_string(), list_path.to_str().unwrap().to_string(),
+        "-c".to_string(), "copy".to_string(),
+        "-y".to_string(),
+        output.clone()
+    ];
+
+    let mut child = ffmpeg_command()
+        .args(&args)
+        .spawn()
+        .map_err(|e| format!("Failed to start ffmpeg merge: {}", e))?;
+
+    let status = child.wait().await.map_err(|e| format!("Process error during merge: {}", e))?;
+    let _ = std::fs::remove_file(list_path);
+
+    if !status.success() {
+        return Err("FFmpeg merge failed".to_string());
+    }
+
+    let _ = app.emit("ffmpeg-progress", Progress {
+        percent: 100.0,
+        time: 0.0,
+        speed: "Done".to_string(),
+        size: "Complete".to_string(),
+    });
+
+    Ok(())
+}
+
+/// Package an input into an adaptive-bitrate output set (HLS or DASH) with a
+/// master/MPD manifest, one variant per ladder rung. Returns the manifest path.
+#[tauri::command]
+pub async fn package_adaptive(
+    app: AppHandle,
+    input: String,
+    output_dir: String,
+    ladder: Vec<Rendition>,
+    format: String,
+) -> Result<String, String> {
+    CANCELLED.store(false, Ordering::SeqCst);
+
+    if ladder.is_empty() {
+        return Err("Ladder must contain at least one rendition".to_string());
+    }
+
+    let dir = std::path::PathBuf::from(&output_dir);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+    // Duration for progress reporting.
+    let probe_output = ffprobe_command()
+        .args(["-v", "error", "-show_format", &input])
+        .output()
+        .await
+        .map_err(|e| format!("Failed to probe: {}", e))?;
+    let total_duration = parse_duration(&String::from_utf8_lossy(&probe_output.stdout));
+
+    match format.as_str() {
+        "dash" => package_dash(&app, &input, &dir, &ladder, total_duration).await,
+        _ => package_hls(&app, &input, &dir, &ladder, total_duration).await,
+    }
+}
+
+/// Keyframe-alignment args shared across renditions so segments line up for
+/// clean ABR switching: a keyframe is forced every 4s (the segment length), and
+/// the GOP is capped at 48 frames with scene-cut keyframes disabled.
+fn keyframe_args() -> Vec<String> {
+    vec![
+        "-g".to_string(),
+        "48".to_string(),
+        "-keyint_min".to_string(),
+        "48".to_string(),
+        "-sc_threshold".to_string(),
+        "0".to_string(),
+        "-force_key_frames".to_string(),
+        "expr:gte(t,n_forced*4)".to_string(),
+    ]
+}
+
+/// Encode each rung to its own HLS variant playlist, then write a master.m3u8.
+async fn package_hls(
+    app: &AppHandle,
+    input: &str,
+    dir: &std::path::Path,
+    ladder: &[Rendition],
+    total_duration: f64,
+) -> Result<String, String> {
+    let total = ladder.len() as u32;
+    for (i, r) in ladder.iter().enumerate() {
+        if CANCELLED.load(Ordering::SeqCst) {
+            return Err("Cancelled by user".to_string());
+        }
+        let variant = dir.join(format!("stream_{}.m3u8", i));
+        let segments = dir.join(format!("stream_{}_%03d.ts", i));
+
+        let mut args = vec![
+            "-y".to_string(),
+            "-i".to_string(),
+            input.to_string(),
+            "-vf".to_string(),
+            format!("scale={}:{}", r.width, r.height),
+            "-c:v".to_string(),
+            "libx264".to_string(),
+            "-b:v".to_string(),
+            r.video_bitrate.to_string(),
+            "-maxrate".to_string(),
+            r.video_bitrate.to_string(),
+            "-bufsize".to_string(),
+            (r.video_bitrate * 2).to_string(),
+        ];
+        args.extend(keyframe_args());
+        args.extend([
+            "-c:a".to_string(),
+            "aac".to_string(),
+            "-b:a".to_string(),
+            r.audio_bitrate.to_string(),
+            "-f".to_string(),
+            "hls".to_string(),
+            "-hls_time".to_string(),
+            "4".to_string(),
+            "-hls_playlist_type".to_string(),
+            "vod".to_string(),
+            "-hls_segment_filename".to_string(),
+            segments.to_string_lossy().to_string(),
+            variant.to_string_lossy().to_string(),
+        ]);
+
+        run_ffmpeg_pass(app, &args, total_duration, i as u32 + 1, total).await?;
+    }
+
+    // Master playlist listing each variant with its bandwidth and resolution.
+    let mut master = String::from("#EXTM3U\n#EXT-X-VERSION:3\n");
+    for (i, r) in ladder.iter().enumerate() {
+        let bandwidth = r.video_bitrate + r.audio_bitrate;
+        master.push_str(&format!(
+            "#EXT-X-STREAM-INF:BANDWIDTH={},RESOLUTION={}x{}\nstream_{}.m3u8\n",
+            bandwidth, r.width, r.height, i
+        ));
+    }
+    let master_path = dir.join("master.m3u8");
+    std::fs::write(&master_path, master)
+        .map_err(|e| format!("Failed to write master playlist: {}", e))?;
+    Ok(master_path.to_string_lossy().to_string())
+}
+
+/// Encode all rungs in one pass into a DASH MPD with aligned segments.
+async fn package_dash(
+    app: &AppHandle,
+    input: &str,
+    dir: &std::path::Path,
+    ladder: &[Rendition],
+    total_duration: f64,
+) -> Result<String, String> {
+    let n = ladder.len();
+
+    // filter_complex: split the source and scale each branch to its rung.
+    let mut fc = format!("[0:v]split={}", n);
+    for i in 0..n {
+        fc.push_str(&format!("[in{}]", i));
+    }
+    fc.push(';');
+    for (i, r) in ladder.iter().enumerate() {
+        fc.push_str(&format!("[in{}]scale={}:{}[v{}];", i, r.width, r.height, i));
+    }
+    let fc = fc.trim_end_matches(';').to_string();
+
+    let mut args = vec![
+        "-y".to_string(),
+        "-i".to_string(),
+        input.to_string(),
+        "-filter_complex".to_string(),
+        fc,
+    ];
+    // One video + one audio mapping per rung.
+    for i in 0..n {
+        args.push("-map".to_string());
+        args.push(format!("[v{}]", i));
+    }
+    for _ in 0..n {
+        args.push("-map".to_string());
+        args.push("0:a".to_string());
+    }
+    // Per-output bitrates.
+    for (i, r) in ladder.iter().enumerate() {
+        args.push(format!("-b:v:{}", i));
+        args.push(r.video_bitrate.to_string());
+        args.push(format!("-b:a:{}", i));
+        args.push(r.audio_bitrate.to_string());
+    }
+    args.extend(["-c:v".to_string(), "libx264".to_string()]);
+    args.extend(["-c:a".to_string(), "aac".to_string()]);
+    args.extend(keyframe_args());
+    args.extend([
+        "-adaptation_sets".to_string(),
+        "id=0,streams=v id=1,streams=a".to_string(),
+        "-seg_duration".to_string(),
+        "4".to_string(),
+        "-use_template".to_string(),
+        "1".to_string(),
+        "-use_timeline".to_string(),
+        "1".to_string(),
+        "-f".to_string(),
+        "dash".to_string(),
+    ]);
+    let manifest = dir.join("manifest.mpd");
+    args.push(manifest.to_string_lossy().to_string());
+
+    run_ffmpeg_pass(app, &args, total_duration, 1, 1).await?;
+    Ok(manifest.to_string_lossy().to_string())
+}
+
+/// Probe the installed FFmpeg for its version plus the encoders and filters it
+/// supports, so the frontend can validate codec/filter choices up front instead
+/// of discovering an unavailable encoder mid-encode.
+#[tauri::command]
+pub async fn check_ffmpeg() -> Result<FfmpegCapabilities, String> {
+    let version_out = ffmpeg_command()
+        .args(["-version"])
+        .output()
+        .await
+        .map_err(|_| "FFmpeg not found. Please install FFmpeg.".to_string())?;
+    let version = String::from_utf8_lossy(&version_out.stdout)
+        .lines()
+        .next()
+        .unwrap_or("Unknown version")
+        .to_string();
+
+    let encoders_out = ffmpeg_command()
+        .args(["-hide_banner", "-encoders"])
+        .output()
+        .await
+        .map_err(|e| format!("Failed to list encoders: {}", e))?;
+    let (video_encoders, audio_encoders) =
+        parse_encoders(&String::from_utf8_lossy(&encoders_out.stdout));
+
+    let filters_out = ffmpeg_command()
+        .args(["-hide_banner", "-filters"])
+        .output()
+        .await
+        .map_err(|e| format!("Failed to list filters: {}", e))?;
+    let filters = parse_filters(&String::from_utf8_lossy(&filters_out.stdout));
+
+    Ok(FfmpegCapabilities {
+        version,
+        video_encoders,
+        audio_encoders,
+        filters,
+    })
+}
+
+/// Parse `ffmpeg -encoders` into `(video, audio)` encoder-name lists. Each row
+/// begins with a capability column whose first letter is the media type.
+fn parse_encoders(output: &str) -> (Vec<String>, Vec<String>) {
+    let mut video = Vec::new();
+    let mut audio = Vec::new();
+    // The legend block (e.g. `V..... = Video`) precedes a `------` separator and
+    // mimics real rows; skip everything up to and including it.
+    let mut in_table = false;
+    for line in output.lines() {
+        let line = line.trim_end();
+        if !in_table {
+            in_table = line.trim_start().starts_with("------");
+            continue;
+        }
+        let Some((flags, rest)) = line.trim_start().split_once(' ') else {
+            continue;
+        };
+        // Capability flags are exactly six characters, e.g. "V....D".
+        if flags.len() != 6 || !flags.is_ascii() {
+            continue;
+        }
+        let Some(name) = rest.split_whitespace().next() else {
+            continue;
+        };
+        match flags.as_bytes()[0] {
+            b'V' => video.push(name.to_string()),
+            b'A' => audio.push(name.to_string()),
+            _ => {}
+        }
+    }
+    (video, audio)
+}
+
+/// Parse `ffmpeg -filters` into a list of available filter names.
+fn parse_filters(output: &str) -> Vec<String> {
+    let mut filters = Vec::new();
+    // As with encoders, the legend (e.g. `T.. = Timeline support`) sits above a
+    // `------` separator and must be skipped before reading the table.
+    let mut in_table = false;
+    for line in output.lines() {
+        if !in_table {
+            in_table = line.trim_start().starts_with("------");
+            continue;
+        }
+        let mut parts = line.trim_start().split_whitespace();
+        let Some(flags) = parts.next() else {
+            continue;
+        };
+        // Filter rows lead with a 3-char capability column, e.g. "TSC".
+        if flags.len() != 3 || !flags.chars().all(|c| c.is_ascii_alphabetic() || c == '.') {
+            continue;
+        }
+        if let Some(name) = parts.next() {
+            filters.push(name.to_string());
+        }
+    }
+    filters
+}
+
+/// Parse a yt-dlp JSON entry into a [`VideoInfo`].
+fn parse_video_info(json: &serde_json::Value) -> VideoInfo {
+    let formats = json["formats"]
+        .as_array()
+        .map(|list| {
+            list.iter()
+                .filter_map(|f| {
+                    let format_id = f["format_id"].as_str()?.to_string();
+                    Some(VideoFormat {
+                        format_id,
+                        ext: f["ext"].as_str().unwrap_or("").to_string(),
+                        resolution: f["resolution"]
+                            .as_str()
+                            .map(|s| s.to_string())
+                            .or_else(|| match (f["width"].as_u64(), f["height"].as_u64()) {
+                                (Some(w), Some(h)) => Some(format!("{}x{}", w, h)),
+                                _ => None,
+                            }),
+                        filesize: f["filesize"]
+                            .as_u64()
+                            .or_else(|| f["filesize_approx"].as_u64()),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    VideoInfo {
+        title: json["title"].as_str().unwrap_or("Unknown").to_string(),
+        uploader: json["uploader"].as_str().map(|s| s.to_string()),
+        duration: json["duration"].as_f64(),
+        thumbnail: json["thumbnail"].as_str().map(|s| s.to_string()),
+        formats,
+    }
+}
+
+/// Fetch structured metadata for a URL so the frontend can offer a format
+/// picker (and handle playlists) before downloading.
+#[tauri::command]
+pub async fn get_video_info(url: String) -> Result<YoutubeDlOutput, String> {
+    let _ = ytdlp_command()
+        .arg("--version")
+        .output()
+        .await
+        .map_err(|_| "yt-dlp not found. Please install yt-dlp.".to_string())?;
+
+    let output = ytdlp_command()
+        .args(["--dump-single-json", "--no-warnings", &url])
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run yt-dlp: {}", e))?;
+
+    if !output.status.success() {
+        return Err("yt-dlp could not read this URL".to_string());
+    }
+
+    let json: serde_json::Value = serde_json::from_str(&String::from_utf8_lossy(&output.stdout))
+        .map_err(|e| format!("Failed to parse yt-dlp JSON: {}", e))?;
+
+    if json["_type"].as_str() == Some("playlist") {
+        let entries = json["entries"]
+            .as_array()
+            .map(|list| list.iter().map(parse_video_info).collect())
+            .unwrap_or_default();
+        Ok(YoutubeDlOutput::Playlist(PlaylistInfo {
+            title: json["title"].as_str().map(|s| s.to_string()),
+            entries,
+        }))
+    } else {
+        Ok(YoutubeDlOutput::Video(parse_video_info(&json)))
+    }
+}
+
+#[tauri::command]
+pub async fn download_video(
+    app: AppHandle,
+    url: String,
+    output_dir: String,
+    format_id: Option<String>,
+) -> Result<String, String> {
+    CANCELLED.store(false, Ordering::SeqCst);
+
+    // Check if yt-dlp is available
+    let _ = ytdlp_command()
+        .arg("--version")
+        .output()
+        .await
+        .map_err(|_| "yt-dlp not found. Please install yt-dlp.".to_string())?;
+
+    let mut args: Vec<String> = vec![
+        "-o".to_string(),
+        format!("{}/%(title)s.%(ext)s", output_dir),
+        "--newline".to_string(),
+        // Print the final (post-merge) path on stdout instead of scraping logs.
+        "--print".to_string(),
+        "after_move:filepath".to_string(),
+        "--no-simulate".to_string(),
+    ];
+    if let Some(ref fmt) = format_id {
+        args.push("-f".to_string());
+        args.push(fmt.clone());
+    }
+    args.push(url);
+
+    let mut child = ytdlp_command()
+        .args(&args)
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start yt-dlp: {}", e))?;
+
+    let stdout = child.stdout.take().unwrap();
+    let mut reader = BufReader::new(stdout).lines();
+    let item_re = regex::Regex::new(r"Downloading item (\d+) of (\d+)").unwrap();
+    let percent_re = regex::Regex::new(r"(\d+\.?\d*)%").unwrap();
+    let mut filenames: Vec<String> = Vec::new();
+
+    while let Ok(Some(line)) = reader.next_line().await {
+        if CANCELLED.load(Ordering::SeqCst) {
+            let _ = child.kill().await;
+            return Err("Cancelled by user".to_string());
+        }
+
+        if line.starts_with("[download]") {
+            if let Some(caps) = item_re.captures(&line) {
+                // Per-item progress for playlists.
+                let _ = app.emit(
+                    "download-progress",
+                    Progress {
+                        percent: 0.0,
+                        time: 0.0,
+                        speed: String::new(),
+                        size: format!("item {}/{}", &caps[1], &caps[2]),
+                    },
+                );
+            } else if let Some(caps) = percent_re.captures(&line) {
+                if let Ok(percent) = caps[1].parse::<f64>() {
+                    let _ = app.emit(
+                        "download-progress",
+                        Progress {
+                            percent,
+                            time: 0.0,
+                            speed: String::new(),
+                            size: String::new(),
+                        },
+                    );
+                }
+            }
+        } else if !line.trim().is_empty() {
+            // A bare line is the `after_move:filepath` of a finished item.
+            filenames.push(line.trim().to_string());
+        }
+    }
+
+    let status = child.wait().await.map_err(|e| format!("yt-dlp process error: {}", e))?;
+
+    if !status.success() {
+        return Err("Download failed".to_string());
+    }
+
+    match filenames.len() {
+        0 => Ok("Download complete (check output folder)".to_string()),
+        1 => Ok(filenames.remove(0)),
+        n => Ok(format!("Downloaded {} items to {}", n, output_dir)),
+    }
+}
+
+/// Generate a preview frame for the current settings
+#[tauri::command]
+pub async fn generate_preview(_app: AppHandle, options: ConvertOptions, timestamp: f64) -> Result<String, String> {
+    
+    // Build ffmpeg command
+    let mut args = Vec::new();
+
+    args.push("-y".to_string());
+    args.push("-ss".to_string());
+    args.push(format!("{:.3}", timestamp));
+    
+    args.push("-i".to_string());
+    args.push(options.input.clone());
+
+    // Image Overlay Input
+    if let Some(ref overlays) = options.overlays {
+        if let Some(ref img) = overlays.image {
+            args.push("-i".to_string());
+            args.push(img.path.clone());
+        }
+    }
+
+    // Filter Chain
+    let (filter_args, _) = build_filter_chain(&options, 0.0); // Duration doesn't matter for single frame preview except for fade, which uses options.end_time or duration calc 
+    // Optimization: Exclude audio filters for image preview
+    let filter_args_video: Vec<String> = filter_args.into_iter().filter(|a| !a.contains("afade") && !a.contains("atempo") && !a.contains("volume") && !a.contains("equalizer") && !a.contains("acompressor") && !a.contains("loudnorm") && !a.contains("afftdn") && !a.eq("-af") && !a.eq("-map") && !a.eq("0:a")).collect();
+    
+    args.extend(filter_args_video);
+
+    args.push("-vframes".to_string());
+    args.push("1".to_string());
+    
+    // Output format: JPEG pipe
+    args.push("-f".to_string());
+    args.push("image2".to_string());
+    args.push("-".to_string()); // Output to stdout
+
+    let mut cmd = ffmpeg_command();
+    cmd.args(&args);
+    
+    // Tauri's Command::output() returns a Result<Output, CommandError>
+    // We need to map errors correctly
+    let output = cmd.output().await.map_err(|e| format!("Failed to run ffmpeg preview: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("FFmpeg preview failed: {}", stderr));
+    }
+
+    // Convert to base64
+    use base64::{Engine as _, engine::general_purpose};
+    let b64 = general_purpose::STANDARD.encode(&output.stdout);
+    Ok(format!("data:image/jpeg;base64,{}", b64))
+}
@@ -0,0 +1,58 @@
+use std::sync::OnceLock;
+
+use tokio::process::Command;
+
+use crate::models::{ToolConfig, ToolSettings};
+
+static CONFIG: OnceLock<ToolConfig> = OnceLock::new();
+
+/// The process-wide tool configuration.
+///
+/// Loaded once from the file named by `$FFMPEG_EDITOR_CONFIG` (TOML or JSON,
+/// chosen by extension). A missing or unreadable file falls back to defaults,
+/// so the app works out of the box with plain `ffmpeg`/`ffprobe`/`yt-dlp`.
+pub fn config() -> &'static ToolConfig {
+    CONFIG.get_or_init(load_tool_config)
+}
+
+fn load_tool_config() -> ToolConfig {
+    let Some(path) = std::env::var_os("FFMPEG_EDITOR_CONFIG") else {
+        return ToolConfig::default();
+    };
+    let path = std::path::PathBuf::from(path);
+    let Ok(raw) = std::fs::read_to_string(&path) else {
+        return ToolConfig::default();
+    };
+    let parsed = if path.extension().and_then(|e| e.to_str()) == Some("json") {
+        serde_json::from_str(&raw).ok()
+    } else {
+        toml::from_str(&raw).ok()
+    };
+    parsed.unwrap_or_default()
+}
+
+impl ToolSettings {
+    /// Build a [`Command`] for this tool, preferring the configured executable
+    /// path and prepending any configured global args.
+    fn command(&self, default_exe: &str) -> Command {
+        let exe = self.executable_path.as_deref().unwrap_or(default_exe);
+        let mut cmd = Command::new(exe);
+        cmd.args(&self.args);
+        cmd
+    }
+}
+
+/// A preconfigured `ffmpeg` command honouring the user's tool config.
+pub fn ffmpeg_command() -> Command {
+    config().ffmpeg.command("ffmpeg")
+}
+
+/// A preconfigured `ffprobe` command honouring the user's tool config.
+pub fn ffprobe_command() -> Command {
+    config().ffprobe.command("ffprobe")
+}
+
+/// A preconfigured `yt-dlp` command honouring the user's tool config.
+pub fn ytdlp_command() -> Command {
+    config().ytdlp.command("yt-dlp")
+}
@@ -1,10 +1,683 @@
-use crate::models::ConvertOptions;
+use crate::models::{ConvertOptions, HwAccel, SegmentSettings, TimelineSettings};
+use std::path::Path;
+
+/// Build an `atempo` chain for an arbitrary speed factor. A single `atempo`
+/// instance only accepts a factor in `[0.5, 2.0]`, so fast factors are split
+/// into `2.0` stages and slow factors into `0.5` stages until the remainder
+/// lands back inside the supported range.
+fn atempo_chain(mut factor: f64) -> String {
+    let mut stages = Vec::new();
+    while factor > 2.0 {
+        stages.push("atempo=2.0".to_string());
+        factor /= 2.0;
+    }
+    while factor < 0.5 {
+        stages.push("atempo=0.5".to_string());
+        factor /= 0.5;
+    }
+    stages.push(format!("atempo={}", factor));
+    stages.join(",")
+}
+
+/// Merge overlapping/adjacent `[from, to]` ranges, clamped to `[start, end]`.
+fn merge_ranges(ranges: &[[f64; 2]], start: f64, end: f64) -> Vec<(f64, f64)> {
+    let mut clamped: Vec<(f64, f64)> = ranges
+        .iter()
+        .map(|r| (r[0].max(start), r[1].min(end)))
+        .filter(|(a, b)| b > a)
+        .collect();
+    clamped.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut merged: Vec<(f64, f64)> = Vec::new();
+    for (a, b) in clamped {
+        match merged.last_mut() {
+            Some(last) if a <= last.1 => last.1 = last.1.max(b),
+            _ => merged.push((a, b)),
+        }
+    }
+    merged
+}
+
+/// Split `[start, end]` into contiguous `(from, to, factor)` segments, where
+/// segments inside a merged fast range carry the speed factor.
+fn build_segments(
+    start: f64,
+    end: f64,
+    fast: &[(f64, f64)],
+    factor: f64,
+) -> Vec<(f64, f64, f64)> {
+    let mut points = vec![start, end];
+    for (a, b) in fast {
+        points.push(*a);
+        points.push(*b);
+    }
+    points.retain(|p| *p >= start && *p <= end);
+    points.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    points.dedup_by(|a, b| (*a - *b).abs() < f64::EPSILON);
+
+    let mut segments = Vec::new();
+    for pair in points.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        if b <= a {
+            continue;
+        }
+        let mid = (a + b) / 2.0;
+        let in_fast = fast.iter().any(|(fa, fb)| mid >= *fa && mid <= *fb);
+        segments.push((a, b, if in_fast { factor } else { 1.0 }));
+    }
+    segments
+}
+
+/// Map an original-source timestamp onto the compressed output timeline.
+fn map_time(segments: &[(f64, f64, f64)], t: f64) -> f64 {
+    let mut out = 0.0;
+    for (s, e, f) in segments {
+        if t < *s {
+            return out;
+        }
+        if t <= *e {
+            return out + (t - s) / f;
+        }
+        out += (e - s) / f;
+    }
+    out
+}
+
+/// Sibling builder for the declarative timeline subsystem. Generates a
+/// `filter_complex` graph that trims to `[start, end]`, speed-ramps the `fast`
+/// ranges, concatenates the segments, and burns in the timestamped captions.
+///
+/// Returns the extra filter args and `uses_complex = true`. An empty segment
+/// list (nothing to cut or ramp) degrades to `(vec![], false)` so the caller
+/// falls back to the existing single-range behavior.
+pub fn build_timeline_filter(
+    timeline: &TimelineSettings,
+    total_duration: f64,
+) -> (Vec<String>, bool) {
+    let start = timeline.start.max(0.0);
+    let end = if timeline.end > start {
+        timeline.end
+    } else {
+        total_duration
+    };
+    if end <= start {
+        return (Vec::new(), false);
+    }
+
+    let factor = if timeline.fast_factor > 0.0 {
+        timeline.fast_factor
+    } else {
+        2.0
+    };
+    let fast = merge_ranges(&timeline.fast, start, end);
+    let segments = build_segments(start, end, &fast, factor);
+
+    // Nothing to ramp or caption and the range is the whole source: fall back.
+    let trivial = fast.is_empty()
+        && timeline.questions.is_empty()
+        && start <= f64::EPSILON
+        && (end - total_duration).abs() < f64::EPSILON;
+    if segments.is_empty() || trivial {
+        return (Vec::new(), false);
+    }
+
+    let mut graph = String::new();
+    let mut concat_labels = String::new();
+    for (i, (s, e, f)) in segments.iter().enumerate() {
+        // Video: trim, reset PTS, and compress PTS for fast segments.
+        if (*f - 1.0).abs() < f64::EPSILON {
+            graph.push_str(&format!(
+                "[0:v]trim=start={}:end={},setpts=PTS-STARTPTS[v{}];",
+                s, e, i
+            ));
+        } else {
+            graph.push_str(&format!(
+                "[0:v]trim=start={}:end={},setpts=(PTS-STARTPTS)/{}[v{}];",
+                s, e, f, i
+            ));
+        }
+        // Audio: matching atrim plus chained atempo for fast segments.
+        if (*f - 1.0).abs() < f64::EPSILON {
+            graph.push_str(&format!(
+                "[0:a]atrim=start={}:end={},asetpts=PTS-STARTPTS[a{}];",
+                s, e, i
+            ));
+        } else {
+            graph.push_str(&format!(
+                "[0:a]atrim=start={}:end={},asetpts=PTS-STARTPTS,{}[a{}];",
+                s,
+                e,
+                atempo_chain(*f),
+                i
+            ));
+        }
+        concat_labels.push_str(&format!("[v{}][a{}]", i, i));
+    }
+
+    let n = segments.len();
+    graph.push_str(&format!("{}concat=n={}:v=1:a=1[cv][ca]", concat_labels, n));
+
+    // Burn captions against the already-trimmed timeline (remapped offsets).
+    let mut video_label = "[cv]".to_string();
+    for (i, q) in timeline.questions.iter().enumerate() {
+        let from = map_time(&segments, q.at);
+        let to = map_time(&segments, q.until);
+        let out_label = format!("[q{}]", i);
+        graph.push_str(&format!(
+            ";{}drawtext=text='{}':x=(w-text_w)/2:y=h-th-20:fontsize=24:fontcolor=white:box=1:boxcolor=black@0.5:enable='between(t,{},{})'{}",
+            video_label,
+            q.text.replace(':', "\\:").replace('\'', "'\\''"),
+            from,
+            to,
+            out_label
+        ));
+        video_label = out_label;
+    }
+
+    let args = vec![
+        "-filter_complex".to_string(),
+        graph,
+        "-map".to_string(),
+        video_label,
+        "-map".to_string(),
+        "[ca]".to_string(),
+    ];
+    (args, true)
+}
+
+/// Decode/init arguments that must be prepended before `-i` for a GPU backend.
+pub fn hw_init_args(hw: &HwAccel) -> Vec<String> {
+    let mut args = Vec::new();
+    match hw.backend.as_str() {
+        "vaapi" => {
+            args.push("-hwaccel".to_string());
+            args.push("vaapi".to_string());
+            args.push("-hwaccel_output_format".to_string());
+            args.push("vaapi".to_string());
+            args.push("-vaapi_device".to_string());
+            args.push(
+                hw.device
+                    .clone()
+                    .unwrap_or_else(|| "/dev/dri/renderD128".to_string()),
+            );
+        }
+        "nvenc" => {
+            args.push("-hwaccel".to_string());
+            args.push("cuda".to_string());
+            args.push("-hwaccel_output_format".to_string());
+            args.push("cuda".to_string());
+            if let Some(ref dev) = hw.device {
+                args.push("-hwaccel_device".to_string());
+                args.push(dev.clone());
+            }
+        }
+        "qsv" => {
+            args.push("-hwaccel".to_string());
+            args.push("qsv".to_string());
+            if let Some(ref dev) = hw.device {
+                args.push("-qsv_device".to_string());
+                args.push(dev.clone());
+            }
+        }
+        "videotoolbox" => {
+            args.push("-hwaccel".to_string());
+            args.push("videotoolbox".to_string());
+        }
+        _ => {}
+    }
+    args
+}
+
+/// Map a requested codec onto the backend's hardware encoder, e.g.
+/// `libx265` + `nvenc` -> `hevc_nvenc`. Returns `None` for unknown backends.
+pub fn hw_encoder(backend: &str, codec: &str) -> Option<String> {
+    let family = if codec.contains("265") || codec.contains("hevc") {
+        "hevc"
+    } else if codec.contains("av1") {
+        "av1"
+    } else {
+        "h264"
+    };
+    let encoder = match backend {
+        "vaapi" => format!("{}_vaapi", family),
+        "nvenc" => format!("{}_nvenc", family),
+        "qsv" => format!("{}_qsv", family),
+        "videotoolbox" => format!("{}_videotoolbox", family),
+        _ => return None,
+    };
+    Some(encoder)
+}
+
+/// Whether a backend runs frames through GPU-resident filters (`scale_*`,
+/// `overlay_*`). VideoToolbox keeps filtering on the CPU.
+fn gpu_filters(backend: &str) -> bool {
+    matches!(backend, "vaapi" | "nvenc" | "qsv")
+}
+
+/// The GPU-resident filter names (produced for `scale`) that operate directly
+/// on hardware surfaces and must not be bounced to system memory.
+fn is_gpu_resident(filter: &str) -> bool {
+    filter.starts_with("scale_vaapi")
+        || filter.starts_with("scale_qsv")
+        || filter.starts_with("scale_npp")
+}
+
+/// Insert `hwdownload`/`hwupload` transitions around every CPU-only filter when
+/// a hardware backend keeps decoded frames as GPU surfaces, so a CPU filter
+/// never sees a hardware surface (which fails with "Impossible to convert
+/// between the formats"). The returned chain always leaves its final frame back
+/// on the GPU, ready for the hardware encoder.
+fn bounce_cpu_filters(filters: Vec<String>) -> Vec<String> {
+    let mut out = Vec::with_capacity(filters.len() + 2);
+    let mut on_gpu = true;
+    for filter in filters {
+        if is_gpu_resident(&filter) {
+            if !on_gpu {
+                out.push("format=nv12,hwupload".to_string());
+                on_gpu = true;
+            }
+        } else if on_gpu {
+            out.push("hwdownload,format=nv12".to_string());
+            on_gpu = false;
+        }
+        out.push(filter);
+    }
+    if !on_gpu {
+        out.push("format=nv12,hwupload".to_string());
+    }
+    out
+}
+
+/// Build the muxer arguments for a segmented / adaptive-streaming output.
+///
+/// `output_dir` is the directory the playlist and segments are written into.
+/// Returns the FFmpeg muxer args plus the path of the playlist/manifest that
+/// should be passed as the output argument.
+pub fn build_segment_args(segment: &SegmentSettings, output_dir: &Path) -> (Vec<String>, String) {
+    let playlist = output_dir.join(&segment.playlist_name);
+    let playlist_str = playlist.to_string_lossy().to_string();
+    let dur = segment.segment_duration;
+
+    let mut args = Vec::new();
+    match segment.mode.as_str() {
+        "hls" | "cmaf" => {
+            let seg_pattern = output_dir.join("segment_%03d.m4s");
+            args.push("-f".to_string());
+            args.push("hls".to_string());
+            args.push("-hls_time".to_string());
+            args.push(format!("{}", dur));
+            args.push("-hls_segment_type".to_string());
+            args.push("fmp4".to_string());
+            args.push("-hls_playlist_type".to_string());
+            // Low-latency outputs are served as an appendable event playlist.
+            args.push(if segment.low_latency { "event" } else { "vod" }.to_string());
+            args.push("-hls_segment_filename".to_string());
+            args.push(seg_pattern.to_string_lossy().to_string());
+            if segment.low_latency {
+                // Independent segments let players fetch CMAF chunks early.
+                args.push("-hls_flags".to_string());
+                args.push("independent_segments".to_string());
+            }
+        }
+        // Default to DASH for "dash" and any unknown mode.
+        _ => {
+            args.push("-f".to_string());
+            args.push("dash".to_string());
+            args.push("-seg_duration".to_string());
+            args.push(format!("{}", dur));
+            args.push("-use_template".to_string());
+            args.push("1".to_string());
+            args.push("-use_timeline".to_string());
+            args.push("1".to_string());
+            if segment.low_latency {
+                args.push("-ldash".to_string());
+                args.push("1".to_string());
+                args.push("-streaming".to_string());
+                args.push("1".to_string());
+            }
+        }
+    }
+
+    (args, playlist_str)
+}
+
+/// Split `[start, end]` into `(from, to, factor)` segments for the speed-ramp,
+/// where segments inside a `fast`/`slow` range carry that range's speed factor.
+fn ramp_segments(options: &ConvertOptions, total_duration: f64) -> Vec<(f64, f64, f64)> {
+    let start = options.start_time.unwrap_or(0.0).max(0.0);
+    let end = options.end_time.unwrap_or(total_duration).max(start);
+    if end <= start {
+        return Vec::new();
+    }
+    let fast_speed = options.fast_speed.unwrap_or(2.0).max(0.01);
+    let slow_speed = options.slow_speed.unwrap_or(0.5).max(0.01);
+    let fast = merge_ranges(&options.fast, start, end);
+    let slow = merge_ranges(&options.slow, start, end);
+
+    let mut points = vec![start, end];
+    for (a, b) in fast.iter().chain(slow.iter()) {
+        points.push(*a);
+        points.push(*b);
+    }
+    points.retain(|p| *p >= start && *p <= end);
+    points.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    points.dedup_by(|a, b| (*a - *b).abs() < f64::EPSILON);
+
+    let mut segments = Vec::new();
+    for pair in points.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        if b <= a {
+            continue;
+        }
+        let mid = (a + b) / 2.0;
+        let factor = if fast.iter().any(|(fa, fb)| mid >= *fa && mid <= *fb) {
+            fast_speed
+        } else if slow.iter().any(|(sa, sb)| mid >= *sa && mid <= *sb) {
+            slow_speed
+        } else {
+            1.0
+        };
+        segments.push((a, b, factor));
+    }
+    segments
+}
+
+/// The compressed output duration after speed-ramping, or `None` when no
+/// `fast`/`slow` ranges are set. Used to keep progress math accurate.
+pub fn speed_ramp_output_duration(options: &ConvertOptions, total_duration: f64) -> Option<f64> {
+    if options.fast.is_empty() && options.slow.is_empty() {
+        return None;
+    }
+    let segments = ramp_segments(options, total_duration);
+    if segments.is_empty() {
+        return None;
+    }
+    Some(segments.iter().map(|(s, e, f)| (e - s) / f).sum())
+}
+
+/// Name the first filter that a speed-ramp render cannot carry, or `None` when
+/// the options are compatible. The ramp builds its own concat filtergraph and
+/// only re-applies color (`eq`) and `scale` afterwards, so every other filter is
+/// unsupported in this combination.
+pub fn speed_ramp_unsupported(options: &ConvertOptions) -> Option<&'static str> {
+    if options.fast.is_empty() && options.slow.is_empty() {
+        return None;
+    }
+    if let Some(ref filters) = options.filters {
+        if filters.hue != 0.0 || filters.blur > 0.0 || filters.sharpen > 0.0 || filters.vignette {
+            return Some("hue/blur/sharpen/vignette filters");
+        }
+    }
+    if options.overlays.is_some() {
+        return Some("image/text overlays");
+    }
+    if options.subtitle_path.is_some() {
+        return Some("burned-in subtitles");
+    }
+    if options.video_transform.is_some() {
+        return Some("rotate/crop/flip/fade transforms");
+    }
+    if options.audio_filters.is_some()
+        || options
+            .audio_volume
+            .map(|v| (v - 1.0).abs() > f32::EPSILON)
+            .unwrap_or(false)
+    {
+        return Some("audio filters");
+    }
+    if options
+        .playback_speed
+        .map(|s| (s - 1.0).abs() > f32::EPSILON)
+        .unwrap_or(false)
+    {
+        return Some("global playback speed");
+    }
+    if options.export_gif.unwrap_or(false) {
+        return Some("GIF export");
+    }
+    if options.extract_thumbnail.unwrap_or(false) {
+        return Some("thumbnail extraction");
+    }
+    None
+}
+
+/// Build the speed-ramp filtergraph: trim to `[start, end]`, apply
+/// `setpts`/`atempo` per segment, and concat back into one continuous stream.
+fn build_speed_ramp(options: &ConvertOptions, total_duration: f64) -> (Vec<String>, bool) {
+    let segments = ramp_segments(options, total_duration);
+    if segments.is_empty() {
+        return (Vec::new(), false);
+    }
+
+    let mut graph = String::new();
+    let mut concat_labels = String::new();
+    for (i, (s, e, f)) in segments.iter().enumerate() {
+        graph.push_str(&format!(
+            "[0:v]trim=start={}:end={},setpts=(PTS-STARTPTS)/{}[v{}];",
+            s, e, f, i
+        ));
+        graph.push_str(&format!(
+            "[0:a]atrim=start={}:end={},asetpts=PTS-STARTPTS,{}[a{}];",
+            s,
+            e,
+            atempo_chain(*f),
+            i
+        ));
+        concat_labels.push_str(&format!("[v{}][a{}]", i, i));
+    }
+    let n = segments.len();
+    graph.push_str(&format!("{}concat=n={}:v=1:a=1[cv][ca]", concat_labels, n));
+
+    // Apply common color/scale filters to the concatenated video so they are
+    // not silently dropped by the temporal remap.
+    let mut post = Vec::new();
+    if let Some(ref filters) = options.filters {
+        post.push(format!(
+            "eq=brightness={}:contrast={}:saturation={}:gamma={}",
+            filters.brightness, filters.contrast, filters.saturation, filters.gamma
+        ));
+    }
+    if options.width.is_some() || options.height.is_some() {
+        let w = options.width.map(|v| v.to_string()).unwrap_or("-1".to_string());
+        let h = options.height.map(|v| v.to_string()).unwrap_or("-1".to_string());
+        post.push(format!("scale={}:{}", w, h));
+    }
+
+    let video_label = if post.is_empty() {
+        "[cv]".to_string()
+    } else {
+        graph.push_str(&format!(";[cv]{}[outv]", post.join(",")));
+        "[outv]".to_string()
+    };
+
+    let args = vec![
+        "-filter_complex".to_string(),
+        graph,
+        "-map".to_string(),
+        video_label,
+        "-map".to_string(),
+        "[ca]".to_string(),
+    ];
+    (args, true)
+}
+
+/// The codec decision for an encode: which encoders to use, and whether each
+/// track can be stream-copied instead of re-encoded.
+#[derive(Debug, Clone)]
+pub struct CodecPlan {
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>,
+    pub copy_video: bool,
+    pub copy_audio: bool,
+}
+
+/// Collapse an encoder/decoder name to a container-level codec family, e.g.
+/// `libx264` and `h264` both map to `"h264"`.
+fn codec_family(codec: &str) -> &str {
+    match codec {
+        "libx264" | "h264" | "avc" => "h264",
+        "libx265" | "hevc" | "h265" => "hevc",
+        "libsvtav1" | "libaom-av1" | "av1" => "av1",
+        "libvpx-vp9" | "vp9" => "vp9",
+        "libvpx" | "vp8" => "vp8",
+        "mpeg4" | "libxvid" => "mpeg4",
+        "aac" | "libfdk_aac" => "aac",
+        "libmp3lame" | "mp3" => "mp3",
+        "libopus" | "opus" => "opus",
+        "libvorbis" | "vorbis" => "vorbis",
+        other => other,
+    }
+}
+
+/// Default (video, audio) encoders for a container extension.
+fn container_defaults(ext: &str) -> (Option<&'static str>, Option<&'static str>) {
+    match ext {
+        "mp4" | "m4v" | "mov" => (Some("libx264"), Some("aac")),
+        "webm" => (Some("libsvtav1"), Some("libopus")),
+        "mkv" => (Some("libx264"), Some("aac")),
+        "avi" => (Some("mpeg4"), Some("libmp3lame")),
+        _ => (None, None),
+    }
+}
+
+/// Allowed (video, audio) codec families for a container, or `None` when the
+/// container is permissive (e.g. Matroska) and needs no validation.
+fn allowed_families(ext: &str) -> Option<(&'static [&'static str], &'static [&'static str])> {
+    match ext {
+        "mp4" | "m4v" | "mov" => Some((
+            &["h264", "hevc", "av1", "mpeg4"],
+            &["aac", "mp3", "ac3", "alac"],
+        )),
+        "webm" => Some((&["vp8", "vp9", "av1"], &["opus", "vorbis"])),
+        "avi" => Some((&["mpeg4", "h264"], &["mp3", "ac3"])),
+        _ => None,
+    }
+}
+
+/// Whether any video-altering work is requested (forces a re-encode).
+fn has_active_video_filters(options: &ConvertOptions) -> bool {
+    options.filters.is_some()
+        || options.overlays.is_some()
+        || options.video_transform.is_some()
+        || options.subtitle_path.is_some()
+        || options.timeline.is_some()
+        || !options.fast.is_empty()
+        || !options.slow.is_empty()
+        || options.width.is_some()
+        || options.height.is_some()
+        || options.export_gif.unwrap_or(false)
+        || options.extract_thumbnail.unwrap_or(false)
+        || options
+            .playback_speed
+            .map(|s| (s - 1.0).abs() > f32::EPSILON)
+            .unwrap_or(false)
+}
+
+/// Whether any audio-altering work is requested (forces a re-encode).
+fn has_active_audio_filters(options: &ConvertOptions) -> bool {
+    options.audio_filters.is_some()
+        || options.timeline.is_some()
+        || !options.fast.is_empty()
+        || !options.slow.is_empty()
+        || options
+            .audio_volume
+            .map(|v| (v - 1.0).abs() > f32::EPSILON)
+            .unwrap_or(false)
+        || options
+            .playback_speed
+            .map(|s| (s - 1.0).abs() > f32::EPSILON)
+            .unwrap_or(false)
+}
+
+/// Resolve the codecs for an encode against the output container: fill defaults
+/// for unset codecs, reject pairings the container can't mux, and fall back to
+/// stream-copy when the source already matches and no filters are active.
+pub fn resolve_codecs(
+    options: &ConvertOptions,
+    ext: &str,
+    source_video: Option<&str>,
+    source_audio: Option<&str>,
+) -> Result<CodecPlan, String> {
+    let ext = ext.to_lowercase();
+    let (default_video, default_audio) = container_defaults(&ext);
+    let allowed = allowed_families(&ext);
+
+    // Video track.
+    let video_codec = if options.audio_only {
+        None
+    } else {
+        let requested = options
+            .video_codec
+            .clone()
+            .or_else(|| default_video.map(String::from));
+        if let (Some(codec), Some((video_ok, _))) = (&requested, allowed) {
+            if !video_ok.contains(&codec_family(codec)) {
+                return Err(format!(
+                    "Video codec '{}' is not supported in a .{} container",
+                    codec, ext
+                ));
+            }
+        }
+        requested
+    };
+
+    let copy_video = match (&video_codec, source_video) {
+        (Some(vc), Some(src)) => {
+            codec_family(vc) == codec_family(src) && !has_active_video_filters(options)
+        }
+        _ => false,
+    };
+
+    // Audio track.
+    let audio_codec = {
+        let requested = options
+            .audio_codec
+            .clone()
+            .or_else(|| default_audio.map(String::from));
+        if let (Some(codec), Some((_, audio_ok))) = (&requested, allowed) {
+            if !audio_ok.contains(&codec_family(codec)) {
+                return Err(format!(
+                    "Audio codec '{}' is not supported in a .{} container",
+                    codec, ext
+                ));
+            }
+        }
+        requested
+    };
+
+    let copy_audio = match (&audio_codec, source_audio) {
+        (Some(ac), Some(src)) => {
+            codec_family(ac) == codec_family(src) && !has_active_audio_filters(options)
+        }
+        _ => false,
+    };
+
+    Ok(CodecPlan {
+        video_codec,
+        audio_codec,
+        copy_video,
+        copy_audio,
+    })
+}
 
 /// Helper to build FFmpeg filter arguments
 pub fn build_filter_chain(options: &ConvertOptions, total_duration: f64) -> (Vec<String>, bool) {
     let mut args = Vec::new();
     let mut vf_filters = Vec::new();
 
+    // Variable-speed time ranges need their own concat-based filtergraph and
+    // take precedence over the linear simple-filter path.
+    if !options.fast.is_empty() || !options.slow.is_empty() {
+        return build_speed_ramp(options, total_duration);
+    }
+
+    // GPU backend (if any) that hosts scale_*/overlay_* filters.
+    let gpu_backend = options
+        .hw_accel
+        .as_ref()
+        .map(|hw| hw.backend.as_str())
+        .filter(|b| gpu_filters(b));
+
     // 1. Pre-Scale Transforms (Deinterlace, Denoise)
     if let Some(ref transform) = options.video_transform {
         if transform.deinterlace {
@@ -44,11 +717,17 @@ pub fn build_filter_chain(options: &ConvertOptions, total_duration: f64) -> (Vec
         }
     }
 
-    // 5. Scale
+    // 5. Scale (GPU-resident when a hardware backend is active)
     if options.width.is_some() || options.height.is_some() {
         let w = options.width.map(|v| v.to_string()).unwrap_or("-1".to_string());
         let h = options.height.map(|v| v.to_string()).unwrap_or("-1".to_string());
-        vf_filters.push(format!("scale={}:{}", w, h));
+        let scale_filter = match gpu_backend {
+            Some("vaapi") => "scale_vaapi",
+            Some("qsv") => "scale_qsv",
+            Some("nvenc") => "scale_npp",
+            _ => "scale",
+        };
+        vf_filters.push(format!("{}={}:{}", scale_filter, w, h));
     }
 
     // 6. Post-Scale Transforms (Flip)
@@ -118,14 +797,15 @@ pub fn build_filter_chain(options: &ConvertOptions, total_duration: f64) -> (Vec
             let x_pos = if text.x.is_empty() { "10" } else { &text.x };
             let y_pos = if text.y.is_empty() { "10" } else { &text.y };
             
-            vf_filters.push(format!(
+            let drawtext = format!(
                 "drawtext=text='{}':fontsize={}:fontcolor={}:x={}:y={}",
                 text.content.replace(":", "\\:").replace("'", "'\\''"),
-                text.font_size, 
-                text.color, 
-                x_pos, 
+                text.font_size,
+                text.color,
+                x_pos,
                 y_pos
-            ));
+            );
+            vf_filters.push(drawtext);
         }
     }
 
@@ -134,6 +814,14 @@ pub fn build_filter_chain(options: &ConvertOptions, total_duration: f64) -> (Vec
         vf_filters.push("fps=15,scale=480:-1:flags=lanczos,split[s0][s1];[s0]palettegen[p];[s1][p]paletteuse".to_string());
     }
 
+    // With a GPU backend, decoded frames are hardware surfaces: bounce every
+    // CPU-only filter through system memory so it interleaves cleanly with the
+    // GPU-resident scale/overlay. GIF export runs its own palette sub-graph and
+    // is not a hardware-encode target, so it is left untouched.
+    if gpu_backend.is_some() && !options.export_gif.unwrap_or(false) {
+        vf_filters = bounce_cpu_filters(vf_filters);
+    }
+
     // Construct Video Filter Args
     let has_image_overlay = options.overlays.as_ref()
         .and_then(|o| o.image.as_ref())
@@ -148,13 +836,21 @@ pub fn build_filter_chain(options: &ConvertOptions, total_duration: f64) -> (Vec
         let x_pos = if img.x.is_empty() { "0" } else { &img.x };
         let y_pos = if img.y.is_empty() { "0" } else { &img.y };
         
+        // Use the GPU-resident overlay filter when a hardware backend is active.
+        let overlay_filter = match gpu_backend {
+            Some("vaapi") => "overlay_vaapi",
+            Some("qsv") => "overlay_qsv",
+            Some("nvenc") => "overlay_cuda",
+            _ => "overlay",
+        };
+
         // Use colorchannelmixer for opacity on the overlay input (stream 1)
         // Force RGBA format before mixing to ensure alpha channel exists even for JPGs
         let overlay_chain = if (img.opacity - 1.0).abs() > f32::EPSILON {
-             format!("[1:v]format=rgba,colorchannelmixer=aa={:.2}[ovr];[v1][ovr]overlay=x={}:y={}[outv]", 
-                img.opacity, x_pos, y_pos)
+             format!("[1:v]format=rgba,colorchannelmixer=aa={:.2}[ovr];[v1][ovr]{}=x={}:y={}[outv]",
+                img.opacity, overlay_filter, x_pos, y_pos)
         } else {
-             format!("[v1][1:v]overlay=x={}:y={}[outv]", x_pos, y_pos)
+             format!("[v1][1:v]{}=x={}:y={}[outv]", overlay_filter, x_pos, y_pos)
         };
 
         let filter_comp = format!(
@@ -4,6 +4,7 @@
 mod models;
 mod utils;
 mod ffmpeg;
+mod config;
 mod commands;
 
 use commands::*;
@@ -19,9 +20,11 @@ pub fn run() {
             get_media_info,
             convert_media,
             merge_media,
+            package_adaptive,
             cancel_conversion,
             check_ffmpeg,
             download_video,
+            get_video_info,
             generate_preview
         ])
         .run(tauri::generate_context!())
@@ -50,6 +50,24 @@ pub fn parse_progress(line: &str, duration: f64) -> Option<Progress> {
     None
 }
 
+/// Parse progress during a multi-pass encode, scaling the percent so each pass
+/// occupies an equal slice of the overall 0–100% range (pass 1 = 0–50%, pass 2
+/// = 50–100% for a two-pass encode).
+pub fn parse_progress_pass(
+    line: &str,
+    duration: f64,
+    pass: u32,
+    total_passes: u32,
+) -> Option<Progress> {
+    let mut progress = parse_progress(line, duration)?;
+    if total_passes > 1 {
+        let span = 100.0 / total_passes as f64;
+        let offset = span * pass.saturating_sub(1) as f64;
+        progress.percent = (offset + progress.percent / 100.0 * span).min(100.0);
+    }
+    Some(progress)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -78,4 +96,22 @@ mod tests {
         let line_invalid = "Some random buffer output";
         assert!(parse_progress(line_invalid, duration).is_none());
     }
+
+    #[test]
+    fn test_parse_progress_pass() {
+        let line = "frame= 100 fps= 25 q=28.0 size= 1024kB time=00:00:10.00 bitrate= 838.9kbits/s speed=1.5x";
+        let duration = 20.0;
+
+        // 50% of the clip in pass 1 of 2 maps to 25% overall.
+        let p1 = parse_progress_pass(line, duration, 1, 2).unwrap();
+        assert!((p1.percent - 25.0).abs() < f64::EPSILON);
+
+        // 50% of the clip in pass 2 of 2 maps to 75% overall.
+        let p2 = parse_progress_pass(line, duration, 2, 2).unwrap();
+        assert!((p2.percent - 75.0).abs() < f64::EPSILON);
+
+        // A single pass is unscaled.
+        let single = parse_progress_pass(line, duration, 1, 1).unwrap();
+        assert!((single.percent - 50.0).abs() < f64::EPSILON);
+    }
 }